@@ -41,9 +41,67 @@ export interface NodeData {
     [key: string]: any;
 }
 
+export interface EdgeRef {
+    sourceId: string;
+    sourceOutputIndex: number;
+    targetId: string;
+    targetInputIndex: number;
+}
+
+export interface EdgeUpdate {
+    dirty: string[];
+    removed: EdgeRef[];
+}
+
+// Error object thrown by GraphEngine methods
+export interface EngineError {
+    code: string; // e.g. "NODE_NOT_FOUND", "CYCLE", "TYPE_MISMATCH"
+    message: string;
+    nodeIds: string[];
+    sockets: Array<{ nodeId: string; index: number; direction: 'input' | 'output' }>;
+}
+
+export interface NodeEvalError {
+    code: string;
+    message: string;
+    origin: string; // ID of the node whose logic failed
+    upstreamFailed: boolean;
+}
+
+// Versioned save format produced by GraphEngine.export_graph()
+export interface GraphDocument {
+    version: number;
+    nodes: Array<{ id: string; type: string; data: any[]; version: number; ui?: any }>;
+    edges: EdgeRef[];
+}
+
+export interface MigrationReport {
+    nodes: Array<{ id: string; fromType: string; toType: string; fromVersion: number; toVersion: number }>;
+    droppedEdges: EdgeRef[];
+}
+
+// One structural change; GraphEngine.apply_batch() takes a list of these
+export type GraphOp =
+    | { kind: 'addNode'; id: string; type: string; data?: any[]; ui?: any }
+    | { kind: 'removeNode'; id: string }
+    | ({ kind: 'addEdge' } & EdgeRef)
+    | ({ kind: 'removeEdge' } & EdgeRef)
+    | ({ kind: 'restoreEdge'; sourcePosition: number; targetPosition: number } & EdgeRef)
+    | { kind: 'setData'; id: string; data: any[] }
+    | { kind: 'groupNodes'; groupId: string; ids: string[] }
+    | { kind: 'ungroup'; id: string };
+
+// Returned by GraphEngine.undo()/redo()
+export interface HistoryUpdate {
+    label?: string;
+    dirty: string[];
+    changes: GraphOp[];
+}
+
 export interface NodeValueCache {
     inputs: any[];
     outputs: any[];
+    error?: NodeEvalError;
 }
 
 export interface GraphState {
@@ -51,6 +109,8 @@ export interface GraphState {
     cache: Map<string, any>;
     engine: GraphEngine | null;
     nodeDefinitions: Map<string, NodeSchema>; // New: Store definitions from WASM
+    groupSchemas: Map<string, NodeSchema>; // Per-node schemas of group nodes (sockets come from their interface)
+    scope: string[]; // Groups entered with enterGroup, outermost first
 }
 
 function createGraphStore() {
@@ -58,12 +118,50 @@ function createGraphStore() {
         graph: new Map(),
         cache: new Map(),
         engine: null,
-        nodeDefinitions: new Map()
+        nodeDefinitions: new Map(),
+        groupSchemas: new Map(),
+        scope: []
     });
 
     let wasm: typeof import('$lib/wasm/wasm_lib') | null = null;
     let engine: GraphEngine | null = null;
 
+    // Rebuild UI graph state from what the engine currently holds
+    const syncFromEngine = (clearCache: boolean, dirty: string[] = []) => {
+        if (!engine) return;
+        const doc = JSON.parse(engine.export_graph()) as GraphDocument;
+        const groupSchemas = new Map<string, NodeSchema>();
+        for (const docNode of doc.nodes) {
+            if (docNode.type === 'groupNode') {
+                groupSchemas.set(docNode.id, engine.node_schema(docNode.id) as NodeSchema);
+            }
+        }
+        const scope = engine.scope();
+        update(state => {
+            const newGraph = new Map<string, GNode>();
+            for (const docNode of doc.nodes) {
+                const def = groupSchemas.get(docNode.id) ?? state.nodeDefinitions.get(docNode.type);
+                newGraph.set(docNode.id, {
+                    id: docNode.id,
+                    type: docNode.type,
+                    data: docNode.data,
+                    inputs: (def?.io.inputs ?? []).map(() => []),
+                    outputs: (def?.io.outputs ?? []).map(() => [])
+                });
+            }
+            for (const edge of doc.edges) {
+                newGraph.get(edge.sourceId)?.outputs[edge.sourceOutputIndex].push({ id: edge.targetId, inputIndex: edge.targetInputIndex, type: 'any' });
+                newGraph.get(edge.targetId)?.inputs[edge.targetInputIndex].push({ id: edge.sourceId, outputIndex: edge.sourceOutputIndex, type: 'any' });
+            }
+            const newCache = clearCache ? new Map() : new Map(state.cache);
+            dirty.forEach(d => newCache.delete(d));
+            for (const id of newCache.keys()) {
+                if (!newGraph.has(id)) newCache.delete(id);
+            }
+            return { ...state, graph: newGraph, cache: newCache, groupSchemas, scope };
+        });
+    };
+
     const initPromise = (async () => {
         if (typeof window !== 'undefined') {
             wasm = await import('$lib/wasm/wasm_lib');
@@ -109,11 +207,13 @@ function createGraphStore() {
             try {
                 // Add to WASM
                 engine.add_node(node.id, node.type, node.data);
+                // Deprecated type keys are stored under their replacement
+                const type = engine.resolve_node_type(node.type);
 
                 // Update local state for UI
                 update(state => {
                     const newGraph = new Map(state.graph);
-                    newGraph.set(node.id, node);
+                    newGraph.set(node.id, { ...node, type });
                     return { ...state, graph: newGraph };
                 });
             } catch (e) {
@@ -148,28 +248,39 @@ function createGraphStore() {
             }
         },
 
-        addEdge: async (sourceId: string, sourceOutputIndex: number, targetId: string, targetInputIndex: number) => {
+        addEdge: async (sourceId: string, sourceOutputIndex: number, targetId: string, targetInputIndex: number): Promise<EdgeRef[]> => {
             await initPromise;
-            if (!engine) return;
+            if (!engine || !wasm) return [];
 
             try {
-                const dirty = engine.add_edge(sourceId, sourceOutputIndex, targetId, targetInputIndex) as unknown as string[];
+                // Full sockets are resolved by the engine; replaced edges come back in `removed`
+                const result = engine.add_edge(sourceId, sourceOutputIndex, targetId, targetInputIndex, wasm.ConnectionPolicy.Replace) as unknown as EdgeUpdate;
                 update(state => {
                     const newGraph = new Map(state.graph);
+                    for (const edge of result.removed) {
+                        const oldSource = newGraph.get(edge.sourceId);
+                        const oldTarget = newGraph.get(edge.targetId);
+                        if (oldSource) {
+                            oldSource.outputs[edge.sourceOutputIndex] = oldSource.outputs[edge.sourceOutputIndex].filter(c => !(c.id === edge.targetId && c.inputIndex === edge.targetInputIndex));
+                        }
+                        if (oldTarget) {
+                            oldTarget.inputs[edge.targetInputIndex] = oldTarget.inputs[edge.targetInputIndex].filter(c => !(c.id === edge.sourceId && c.outputIndex === edge.sourceOutputIndex));
+                        }
+                    }
+
                     const source = newGraph.get(sourceId);
                     const target = newGraph.get(targetId);
-                    if (source && target) {
+                    if (source && target && result.dirty.length > 0) {
                         source.outputs[sourceOutputIndex] = [...source.outputs[sourceOutputIndex], { id: targetId, inputIndex: targetInputIndex, type: 'any' }];
                         target.inputs[targetInputIndex] = [...target.inputs[targetInputIndex], { id: sourceId, outputIndex: sourceOutputIndex, type: 'any' }];
                     }
 
                     const newCache = new Map(state.cache);
-                    if (Array.isArray(dirty)) {
-                        dirty.forEach(d => newCache.delete(d));
-                    }
+                    result.dirty.forEach(d => newCache.delete(d));
 
                     return { ...state, graph: newGraph, cache: newCache };
                 });
+                return result.removed;
             } catch (e) {
                 console.error("Failed to add edge:", e);
                 throw e;
@@ -228,6 +339,104 @@ function createGraphStore() {
             }
         },
 
+        // Make a saved graph (export_graph JSON) available as a node type in the AddMenu
+        registerGraphNodeType: async (name: string, json: string): Promise<NodeSchema | null> => {
+            await initPromise;
+            if (!engine) return null;
+            const schema = engine.register_graph_node_type(name, json) as NodeSchema;
+            update(state => {
+                const nodeDefinitions = new Map(state.nodeDefinitions);
+                nodeDefinitions.set(name, schema);
+                return { ...state, nodeDefinitions };
+            });
+            return schema;
+        },
+
+        exportGraph: async (): Promise<string | null> => {
+            await initPromise;
+            if (!engine) return null;
+            return engine.export_graph();
+        },
+
+        importGraph: async (json: string) => {
+            await initPromise;
+            if (!engine) return;
+
+            try {
+                const report = engine.import_graph(json) as unknown as MigrationReport;
+                if (report.nodes.length > 0 || report.droppedEdges.length > 0) {
+                    console.log("Migrated saved graph:", report);
+                }
+                // Re-export so UI state reflects migrated node types and sockets
+                syncFromEngine(true);
+            } catch (e) {
+                console.error("Failed to import graph:", e);
+                throw e;
+            }
+        },
+
+        // All-or-nothing: on error the engine is unchanged and the error is rethrown
+        applyBatch: async (ops: GraphOp[]): Promise<string[]> => {
+            await initPromise;
+            if (!engine) return [];
+            const dirty = engine.apply_batch(ops) as unknown as string[];
+            syncFromEngine(false, dirty);
+            return dirty;
+        },
+
+        // Collapse a selection into a group node
+        groupNodes: async (groupId: string, ids: string[]) => {
+            await initPromise;
+            if (!engine) return;
+            const dirty = engine.group_nodes(groupId, ids) as unknown as string[];
+            syncFromEngine(false, dirty);
+        },
+
+        ungroup: async (id: string): Promise<EdgeRef[]> => {
+            await initPromise;
+            if (!engine) return [];
+            const result = engine.ungroup(id) as unknown as EdgeUpdate;
+            syncFromEngine(false, result.dirty);
+            return result.removed;
+        },
+
+        // Inside a group every other store method edits the inner graph
+        enterGroup: async (id: string) => {
+            await initPromise;
+            if (!engine) return;
+            engine.enter_group(id);
+            syncFromEngine(true);
+        },
+
+        exitGroup: async (): Promise<EdgeRef[]> => {
+            await initPromise;
+            if (!engine) return [];
+            const result = engine.exit_group() as unknown as EdgeUpdate | null;
+            if (!result) return [];
+            syncFromEngine(true);
+            return result.removed;
+        },
+
+        undo: async (): Promise<HistoryUpdate | null> => {
+            await initPromise;
+            if (!engine) return null;
+            const result = engine.undo() as unknown as HistoryUpdate | null;
+            if (result) syncFromEngine(false, result.dirty);
+            return result;
+        },
+
+        redo: async (): Promise<HistoryUpdate | null> => {
+            await initPromise;
+            if (!engine) return null;
+            const result = engine.redo() as unknown as HistoryUpdate | null;
+            if (result) syncFromEngine(false, result.dirty);
+            return result;
+        },
+
+        // Everything between beginGroup and endGroup undoes as one step
+        beginGroup: (label: string) => engine?.begin_group(label),
+        endGroup: () => engine?.end_group(),
+
         evaluateNode: async (id: string) => {
             await initPromise;
             if (!engine) return null;
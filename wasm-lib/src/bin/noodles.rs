@@ -0,0 +1,254 @@
+// Headless runner: loads a saved graph, evaluates its output nodes with the
+// same `GraphEngine` as the app and prints the results as JSON, so graphs can
+// run in scripts and CI.
+//
+//   noodles <graph.json> [--output <id>]... [--set <id>.<index>=<value>]...
+//           [--policy error|non-finite|zero] [--decimal] [--pretty]
+//
+// Each output maps to `{ "value": ... }` or `{ "error": ... }`. Exit codes: 0
+// when every output evaluated, 1 when any failed, 2 when the arguments or the
+// graph file are unusable.
+//
+// Built only with the `cli` feature, without the wasm bindings:
+//   cargo run --no-default-features --features cli -- graph.json
+use serde_json::Value as SerdeValue;
+use std::process::ExitCode;
+use wasm_lib::{GraphDocument, GraphEngine, NumericPolicy};
+
+const OUTPUT_NODE: &str = "outputNode";
+
+const USAGE: &str = "\
+Usage: noodles <graph.json> [options]
+
+Options:
+  --output <id>             Evaluate only this output node (repeatable; default: all)
+  --set <id>.<index>=<json> Override a data value before evaluating (repeatable);
+                            values that are not valid JSON are taken as strings
+  --policy <policy>         Non-finite math results: error, non-finite (default), zero
+  --decimal                 Exact decimal arithmetic (needs the `decimal` feature)
+  --pretty                  Pretty-print the JSON result
+  -h, --help                Show this help";
+
+struct Args {
+    path: String,
+    outputs: Vec<String>,
+    overrides: Vec<Override>,
+    policy: NumericPolicy,
+    decimal: bool,
+    pretty: bool,
+}
+
+// `--set node.1=42`: replaces data slot 1 of `node`
+struct Override {
+    node_id: String,
+    index: usize,
+    value: SerdeValue,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("noodles: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut engine = match load(&args) {
+        Ok(engine) => engine,
+        Err(message) => {
+            eprintln!("noodles: {}", message);
+            return ExitCode::from(2);
+        }
+    };
+
+    let (results, failed) = evaluate(&mut engine, &args.outputs);
+    let results = SerdeValue::Object(results);
+    let json = if args.pretty { serde_json::to_string_pretty(&results) } else { serde_json::to_string(&results) };
+    println!("{}", json.expect("evaluation results are always serializable"));
+
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+// `Ok(None)` when help was asked for
+fn parse_args(mut argv: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut path = None;
+    let mut args = Args {
+        path: String::new(),
+        outputs: Vec::new(),
+        overrides: Vec::new(),
+        policy: NumericPolicy::default(),
+        decimal: false,
+        pretty: false,
+    };
+
+    while let Some(arg) = argv.next() {
+        let mut value = |flag: &str| argv.next().ok_or_else(|| format!("{} needs a value", flag));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--output" => args.outputs.push(value("--output")?),
+            "--set" => args.overrides.push(parse_override(&value("--set")?)?),
+            "--policy" => {
+                args.policy = match value("--policy")?.as_str() {
+                    "error" => NumericPolicy::Error,
+                    "non-finite" => NumericPolicy::NonFinite,
+                    "zero" => NumericPolicy::Zero,
+                    other => return Err(format!("unknown policy `{}`", other)),
+                }
+            }
+            "--decimal" => args.decimal = true,
+            "--pretty" => args.pretty = true,
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+            _ if path.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => path = Some(arg),
+        }
+    }
+
+    args.path = path.ok_or("missing graph file")?;
+    Ok(Some(args))
+}
+
+fn parse_override(spec: &str) -> Result<Override, String> {
+    let invalid = || format!("invalid override `{}` (expected <id>.<index>=<value>)", spec);
+    let (target, raw) = spec.split_once('=').ok_or_else(invalid)?;
+    // Node IDs may contain dots themselves, the index is after the last one
+    let (node_id, index) = target.rsplit_once('.').ok_or_else(invalid)?;
+    let index = index.parse().map_err(|_| invalid())?;
+    let value = serde_json::from_str(raw).unwrap_or_else(|_| SerdeValue::String(raw.to_string()));
+    Ok(Override { node_id: node_id.to_string(), index, value })
+}
+
+fn load(args: &Args) -> Result<GraphEngine, String> {
+    let json = std::fs::read_to_string(&args.path).map_err(|e| format!("cannot read {}: {}", args.path, e))?;
+    let doc = GraphDocument::from_json(&json).map_err(|e| e.to_string())?;
+
+    let mut engine = GraphEngine::new();
+    engine.set_numeric_policy(args.policy);
+    if args.decimal {
+        enable_decimal(&mut engine)?;
+    }
+    engine.import_graph(doc).map_err(|e| e.to_string())?;
+
+    for o in &args.overrides {
+        let node = engine.node(&o.node_id).ok_or_else(|| format!("no node `{}` to override", o.node_id))?;
+        let mut data = node.data.clone();
+        let count = data.len();
+        let slot = data.get_mut(o.index).ok_or_else(|| format!("node `{}` has no data slot {} (it has {})", o.node_id, o.index, count))?;
+        *slot = o.value.clone();
+        engine.update_node_data(&o.node_id, data).map_err(|e| e.to_string())?;
+    }
+
+    Ok(engine)
+}
+
+#[cfg(feature = "decimal")]
+fn enable_decimal(engine: &mut GraphEngine) -> Result<(), String> {
+    engine.set_decimal_mode(true);
+    Ok(())
+}
+
+#[cfg(not(feature = "decimal"))]
+fn enable_decimal(_engine: &mut GraphEngine) -> Result<(), String> {
+    Err("--decimal needs a build with the `decimal` feature".to_string())
+}
+
+// Results keyed by output node ID: `{ "value": ... }` or `{ "error": ... }`, so
+// a value that happens to be an object with an `error` key is not mistaken for
+// a failure. The flag is set when any of them failed.
+fn evaluate(engine: &mut GraphEngine, requested: &[String]) -> (serde_json::Map<String, SerdeValue>, bool) {
+    let mut ids: Vec<String> = if requested.is_empty() {
+        engine.nodes().filter(|n| n.node_type == OUTPUT_NODE).map(|n| n.id.clone()).collect()
+    } else {
+        requested.to_vec()
+    };
+    ids.sort();
+    ids.dedup();
+
+    let mut results = serde_json::Map::new();
+    let mut failed = false;
+    for id in ids {
+        let result = match engine.node(&id) {
+            Some(node) if node.node_type != OUTPUT_NODE => Err(serde_json::json!({
+                "code": "NOT_AN_OUTPUT",
+                "message": format!("Node `{}` is a {}, not an output node.", id, node.node_type),
+            })),
+            _ => match engine.evaluate_node(&id) {
+                Ok(mut trace) => match trace.remove(&id) {
+                    Some(cache) => match cache.error {
+                        Some(error) => Err(serde_json::to_value(error).expect("node errors are always serializable")),
+                        None => Ok(cache.outputs.into_iter().next().unwrap_or(SerdeValue::Null)),
+                    },
+                    None => Ok(SerdeValue::Null),
+                },
+                Err(e) => Err(serde_json::json!({ "code": e.code(), "message": e.to_string() })),
+            },
+        };
+        let entry = match result {
+            Ok(value) => serde_json::json!({ "value": value }),
+            Err(error) => {
+                failed = true;
+                serde_json::json!({ "error": error })
+            }
+        };
+        results.insert(id, entry);
+    }
+    (results, failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(list.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_options_and_overrides() {
+        let parsed = args(&["graph.json", "--output", "out", "--set", "a.b.1=42", "--set", "s.0=hello", "--policy", "zero", "--pretty"]).unwrap().unwrap();
+        assert_eq!(parsed.path, "graph.json");
+        assert_eq!(parsed.outputs, ["out"]);
+        assert_eq!((parsed.overrides[0].node_id.as_str(), parsed.overrides[0].index), ("a.b", 1));
+        assert_eq!(parsed.overrides[0].value, serde_json::json!(42));
+        assert_eq!(parsed.overrides[1].value, serde_json::json!("hello"));
+        assert_eq!(parsed.policy, NumericPolicy::Zero);
+        assert!(parsed.pretty);
+
+        assert!(args(&["--help"]).unwrap().is_none());
+        assert!(args(&[]).is_err());
+        assert!(args(&["g.json", "--set", "no-index=1"]).is_err());
+        assert!(args(&["g.json", "--policy", "loud"]).is_err());
+    }
+
+    #[test]
+    fn evaluates_outputs_and_flags_failures() {
+        let mut engine = GraphEngine::new();
+        engine.set_numeric_policy(NumericPolicy::Error);
+        for (id, node_type, data) in [
+            ("one", "numberNode", vec![serde_json::json!(1)]),
+            ("div", "divisionNode", vec![serde_json::json!(0), serde_json::json!(0)]),
+            ("good", "outputNode", vec![serde_json::json!(" ")]),
+            ("bad", "outputNode", vec![serde_json::json!(" ")]),
+        ] {
+            engine.add_node(id.into(), node_type.into(), data).unwrap();
+        }
+        let policy = wasm_lib::ConnectionPolicy::Reject;
+        engine.add_edge("one".into(), 0, "good".into(), 0, policy).unwrap();
+        engine.add_edge("one".into(), 0, "div".into(), 0, policy).unwrap();
+        engine.add_edge("div".into(), 0, "bad".into(), 0, policy).unwrap();
+
+        let (results, failed) = evaluate(&mut engine, &[]);
+        assert!(failed);
+        assert_eq!(results["good"], serde_json::json!({ "value": 1 }));
+        assert!(results["bad"].get("value").is_none());
+        assert_eq!(results["bad"]["error"]["origin"], "div");
+
+        let (results, failed) = evaluate(&mut engine, &["good".to_string()]);
+        assert!(!failed);
+        assert_eq!(results.len(), 1);
+    }
+}
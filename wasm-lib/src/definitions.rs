@@ -1,6 +1,13 @@
+use crate::functions;
+use crate::group;
+use crate::iteration;
+use crate::numeric::{self, as_number, Num, NumericSettings, Rounding};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as SerdeValue;
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::ops::Deref;
 
 // --- Schema Structs (Sendable to Frontend) ---
 
@@ -16,18 +23,22 @@ pub struct SocketUI {
 pub struct SocketDef {
     pub name: String,
     #[serde(rename = "type")]
-    pub val_type: String, // "boolean", "number", "string", "any"
+    pub val_type: String, // "boolean", "number", "string", "list", "object", "function", "any"
     pub ui: SocketUI,
     #[serde(rename = "maxConnections")]
     pub max_connections: usize, // Use usize, serialize as number
+    // Lazy inputs are not evaluated up front; the logic requests them on demand
+    #[serde(default)]
+    pub lazy: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DataUI {
     #[serde(rename = "type")]
-    pub ui_type: String, // "input", "display"
+    pub ui_type: String, // "input", "display", "select"
     #[serde(rename = "showName")]
     pub show_name: bool,
+    // Choices offered by a "select" dropdown
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<Vec<String>>,
 }
@@ -52,6 +63,8 @@ pub struct IO {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NodeSchema {
     pub name: String,
+    // Bump when sockets or data slots change, and register a `NodeMigration`
+    pub version: u32,
     pub io: IO,
     pub data: Vec<DataDef>,
     // Declarative flags for frontend behavior
@@ -59,12 +72,111 @@ pub struct NodeSchema {
     pub auto_evaluate_on_connect: bool,
 }
 
+// --- Socket Type Compatibility ---
+
+// Declared coercions between socket types as (from, to) pairs: a value of type
+// `from` may flow into a socket of type `to` and is converted on the way in.
+// Identical types always connect, and "any" on either side matches everything.
+// Anything not listed (e.g. string -> number) is refused by `add_edge`.
+pub const TYPE_COERCIONS: &[(&str, &str)] = &[
+    ("number", "string"),
+    ("boolean", "string"),
+];
+
+pub fn check_type_compatibility(from: &str, to: &str) -> Result<(), String> {
+    if from == to || from == "any" || to == "any" || TYPE_COERCIONS.contains(&(from, to)) {
+        Ok(())
+    } else {
+        Err(format!("Cannot connect a `{}` output to a `{}` input.", from, to))
+    }
+}
+
+// Socket type name of a runtime value ("any" for values without a socket type).
+pub fn value_type(value: &SerdeValue) -> &'static str {
+    match value {
+        SerdeValue::Bool(_) => "boolean",
+        SerdeValue::Number(_) => "number",
+        // Tagged NaN / Infinity and decimals, see `numeric`
+        SerdeValue::Object(_) if as_number(value).is_some() => "number",
+        SerdeValue::String(_) => "string",
+        SerdeValue::Array(_) => "list",
+        SerdeValue::Object(_) if functions::function_name(value).is_some() => "function",
+        SerdeValue::Object(_) => "object",
+        _ => "any",
+    }
+}
+
+// Applies the declared coercion for a value entering a socket of type `to`.
+// Values that need no (or have no) coercion are passed through unchanged.
+pub fn coerce_value(value: SerdeValue, to: &str) -> SerdeValue {
+    if !TYPE_COERCIONS.contains(&(value_type(&value), to)) {
+        return value;
+    }
+    match (to, &value) {
+        ("string", SerdeValue::Number(n)) => serde_json::json!(n.to_string()),
+        ("string", SerdeValue::Bool(b)) => serde_json::json!(b.to_string()),
+        ("string", SerdeValue::Object(_)) => SerdeValue::String(display_text(&value)),
+        _ => value,
+    }
+}
+
 // --- Logic Definition ---
 
+// Fetches the values connected to an input socket, evaluating them on demand
+pub type InputFetcher<'a> = Box<dyn FnMut(usize) -> Result<Vec<SerdeValue>, String> + 'a>;
+
+// Inputs handed to node logic. Derefs to one Vec of connected values per
+// input socket, so `inputs[idx]` works as before. Sockets declared `lazy`
+// stay empty until the logic calls `request(idx)`.
+pub struct LogicInputs<'a> {
+    values: Vec<Vec<SerdeValue>>,
+    fetch: RefCell<InputFetcher<'a>>,
+    numeric: NumericSettings,
+}
+
+impl<'a> LogicInputs<'a> {
+    pub fn new(values: Vec<Vec<SerdeValue>>, fetch: InputFetcher<'a>, numeric: NumericSettings) -> Self {
+        LogicInputs { values, fetch: RefCell::new(fetch), numeric }
+    }
+
+    // The engine's number settings: how to read number inputs (decimal mode)
+    // and what to do with results that are not finite numbers
+    pub fn numeric(&self) -> NumericSettings {
+        self.numeric
+    }
+
+    // Values connected to input `idx`, evaluating the upstream nodes if the
+    // socket is lazy. Eager sockets return what was already computed.
+    pub fn request(&self, idx: usize) -> Result<Vec<SerdeValue>, String> {
+        match self.values.get(idx) {
+            Some(vals) if !vals.is_empty() => Ok(vals.clone()),
+            _ => (self.fetch.borrow_mut())(idx),
+        }
+    }
+
+    // First value connected to input `idx` (requested on demand), falling
+    // back to the node's data slot of the same index, then to null.
+    pub fn request_or_data(&self, idx: usize, data: &[SerdeValue]) -> Result<SerdeValue, String> {
+        match self.request(idx)?.into_iter().next() {
+            Some(val) => Ok(val),
+            None => Ok(data.get(idx).cloned().unwrap_or(SerdeValue::Null)),
+        }
+    }
+}
+
+impl Deref for LogicInputs<'_> {
+    type Target = [Vec<SerdeValue>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
 // Function pointer type for node logic
-// Inputs: &[Vec<Value>] (connections), Data: &[Value] (local data)
-// Output: Result<SerdeValue, String>
-pub type LogicFn = Box<dyn Fn(&[Vec<SerdeValue>], &[SerdeValue]) -> Result<SerdeValue, String> + Send + Sync>;
+// Inputs: &LogicInputs (connections), Data: &[Value] (local data)
+// Output: Result<Vec<SerdeValue>, String>, one value per output socket
+// (nodes without outputs, like outputNode, return the value they display)
+pub type LogicFn = Box<dyn Fn(&LogicInputs, &[SerdeValue]) -> Result<Vec<SerdeValue>, String> + Send + Sync>;
 
 // Combined Definition (Registry Item)
 pub struct NodeDefinition {
@@ -72,6 +184,404 @@ pub struct NodeDefinition {
     pub logic: LogicFn,
 }
 
+// --- Migrations ---
+
+// Rewrites the data array of a saved node for the next schema version
+pub type DataMigrateFn = Box<dyn Fn(Vec<SerdeValue>) -> Vec<SerdeValue> + Send + Sync>;
+
+// Upgrades saved nodes of `node_type` at schema `from_version` to `to_version`,
+// optionally renaming the type. Socket maps give the new index for each old
+// index (None drops the edge); indices past the end of a map are kept as is.
+pub struct NodeMigration {
+    pub node_type: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub rename_to: Option<String>,
+    pub inputs: Option<Vec<Option<usize>>>,
+    pub outputs: Option<Vec<Option<usize>>>,
+    pub data: Option<DataMigrateFn>,
+}
+
+impl NodeMigration {
+    pub fn map_input(&self, index: usize) -> Option<usize> {
+        remap_index(&self.inputs, index)
+    }
+
+    pub fn map_output(&self, index: usize) -> Option<usize> {
+        remap_index(&self.outputs, index)
+    }
+}
+
+fn remap_index(map: &Option<Vec<Option<usize>>>, index: usize) -> Option<usize> {
+    match map {
+        Some(map) if index < map.len() => map[index],
+        _ => Some(index),
+    }
+}
+
+// Deprecated type keys `add_node` still accepts, with the type each stands
+// for. Nodes are stored under the new key; saved graphs are renamed by the
+// matching migration instead.
+pub const NODE_TYPE_ALIASES: &[(&str, &str)] = &[
+    ("subractionNode", "subtractionNode"),
+];
+
+pub fn resolve_node_type(node_type: &str) -> &str {
+    NODE_TYPE_ALIASES.iter()
+        .find(|(alias, _)| *alias == node_type)
+        .map_or(node_type, |(_, target)| target)
+}
+
+pub fn get_node_migrations() -> Vec<NodeMigration> {
+    vec![
+        // Typo in the original type key
+        NodeMigration {
+            node_type: "subractionNode".to_string(),
+            from_version: 1,
+            to_version: 1,
+            rename_to: Some("subtractionNode".to_string()),
+            inputs: None,
+            outputs: None,
+            data: None,
+        },
+        // v2 adds the operator and tolerance slots; old nodes keep testing equality
+        NodeMigration {
+            node_type: "comparisonNode".to_string(),
+            from_version: 1,
+            to_version: 2,
+            rename_to: None,
+            inputs: None,
+            outputs: None,
+            data: Some(Box::new(|mut data| {
+                data.resize(2, serde_json::json!(0));
+                data.push(serde_json::json!("=="));
+                data.push(serde_json::json!(DEFAULT_TOLERANCE));
+                data
+            })),
+        },
+    ]
+}
+
+// --- Comparison ---
+
+pub const COMPARISON_OPERATORS: &[&str] = &["==", "!=", "<", "<=", ">", ">="];
+
+// Default absolute tolerance for numbers in `comparisonNode`
+pub const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+// Applies a comparison operator. The rules across types:
+// - numbers compare numerically, and are equal when they differ by at most
+//   `tolerance` (so 1 == 1.0, and 0.1 + 0.2 == 0.3). Decimals compare
+//   exactly (within the tolerance). NaN equals nothing and is neither less
+//   nor greater than anything.
+// - strings compare by code point, booleans as false < true.
+// - lists and objects are equal when structurally equal (numbers inside
+//   compared as above, without tolerance) and cannot be ordered.
+// - values of different types are never equal (no coercion: "1" != 1), and
+//   ordering them is an error. null only equals null.
+pub fn compare_with(operator: &str, a: &SerdeValue, b: &SerdeValue, tolerance: f64) -> Result<bool, String> {
+    let type_name = |v: &SerdeValue| if v.is_null() { "null" } else { value_type(v) };
+    let (type_a, type_b) = (type_name(a), type_name(b));
+    let ordering = if type_a != type_b {
+        None
+    } else if let (Some(x), Some(y)) = (Num::from_value(a), Num::from_value(b)) {
+        match numeric::compare(x, y, tolerance) {
+            Some(ordering) => Some(ordering),
+            None => return Ok(operator == "!="), // NaN
+        }
+    } else {
+        Some(compare_values(a, b))
+    };
+
+    let orderable = matches!(type_a, "number" | "string" | "boolean");
+    match (operator, ordering) {
+        ("==", ordering) => Ok(ordering == Some(Ordering::Equal)),
+        ("!=", ordering) => Ok(ordering != Some(Ordering::Equal)),
+        (_, Some(ordering)) if orderable => match operator {
+            "<" => Ok(ordering.is_lt()),
+            "<=" => Ok(ordering.is_le()),
+            ">" => Ok(ordering.is_gt()),
+            ">=" => Ok(ordering.is_ge()),
+            other => Err(format!("Unknown comparison operator `{}`", other)),
+        },
+        _ if !COMPARISON_OPERATORS.contains(&operator) => Err(format!("Unknown comparison operator `{}`", operator)),
+        _ if type_a == type_b => Err(format!("Cannot order {} values with `{}`", type_a, operator)),
+        _ => Err(format!("Cannot order a {} and a {} with `{}`", type_a, type_b, operator)),
+    }
+}
+
+// --- List Helpers ---
+
+// Longest list `rangeNode` will build
+pub const MAX_RANGE_LENGTH: usize = 1_000_000;
+
+// Total order over values, used to sort and de-duplicate lists:
+// null < boolean < number < string < list < object. Numbers compare
+// numerically (so 1 == 1.0), strings by code point, lists element-wise and
+// objects entry by entry in key order.
+pub fn compare_values(a: &SerdeValue, b: &SerdeValue) -> Ordering {
+    if let (Some(x), Some(y)) = (Num::from_value(a), Num::from_value(b)) {
+        // NaN has no order of its own: it goes after every other number
+        return match (x.as_f64().is_nan(), y.as_f64().is_nan()) {
+            (false, false) => numeric::compare(x, y, 0.0).unwrap_or(Ordering::Equal),
+            (x_nan, y_nan) => x_nan.cmp(&y_nan),
+        };
+    }
+    let rank = |v: &SerdeValue| match v {
+        SerdeValue::Null => 0,
+        SerdeValue::Bool(_) => 1,
+        _ if as_number(v).is_some() => 2,
+        SerdeValue::Number(_) => 2,
+        SerdeValue::String(_) => 3,
+        SerdeValue::Array(_) => 4,
+        SerdeValue::Object(_) => 5,
+    };
+    match (a, b) {
+        (SerdeValue::Bool(x), SerdeValue::Bool(y)) => x.cmp(y),
+        (SerdeValue::String(x), SerdeValue::String(y)) => x.cmp(y),
+        (SerdeValue::Array(x), SerdeValue::Array(y)) => x.iter().zip(y)
+            .map(|(x, y)| compare_values(x, y))
+            .find(|order| order.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (SerdeValue::Object(x), SerdeValue::Object(y)) => x.iter().zip(y)
+            .map(|((key_x, x), (key_y, y))| key_x.cmp(key_y).then_with(|| compare_values(x, y)))
+            .find(|order| order.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+// Input `idx` (or its data slot) as a list
+fn list_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<Vec<SerdeValue>, String> {
+    match inputs.request_or_data(idx, data)? {
+        SerdeValue::Array(items) => Ok(items),
+        other => Err(format!("Expected a list, got {}", other)),
+    }
+}
+
+// Input `idx` (or its data slot) as a whole number (decimals included); null
+// means "not given". Fractions and numbers beyond i64 are errors rather than
+// being cut down.
+fn index_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<Option<i64>, String> {
+    let value = inputs.request_or_data(idx, data)?;
+    if value.is_null() {
+        return Ok(None);
+    }
+    let number = inputs.numeric().read(&value).ok_or_else(|| format!("Expected a number, got {}", value))?;
+    number.as_whole().map(Some).ok_or_else(|| format!("Expected a whole number, got {}", display_text(&value)))
+}
+
+// Negative indices count from the end of the list
+fn resolve_index(index: i64, len: usize) -> i64 {
+    if index < 0 { index + len as i64 } else { index }
+}
+
+// --- Object Helpers ---
+
+// Paths are object keys separated by dots ("user.address.city"); a segment
+// that is a whole number indexes into a list instead. The empty path is the
+// value itself.
+fn path_segments(path: &str) -> Vec<&str> {
+    if path.is_empty() { Vec::new() } else { path.split('.').collect() }
+}
+
+fn object_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<serde_json::Map<String, SerdeValue>, String> {
+    match inputs.request_or_data(idx, data)? {
+        SerdeValue::Object(fields) => Ok(fields),
+        other => Err(format!("Expected an object, got {}", other)),
+    }
+}
+
+fn path_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<String, String> {
+    match inputs.request_or_data(idx, data)? {
+        SerdeValue::String(path) => Ok(path),
+        SerdeValue::Null => Ok(String::new()),
+        other => Err(format!("Expected a key or path, got {}", other)),
+    }
+}
+
+fn child<'v>(value: &'v SerdeValue, segment: &str) -> Option<&'v SerdeValue> {
+    match value {
+        SerdeValue::Object(fields) => fields.get(segment),
+        SerdeValue::Array(items) => segment.parse::<usize>().ok().and_then(|index| items.get(index)),
+        _ => None,
+    }
+}
+
+fn child_mut<'v>(value: &'v mut SerdeValue, segment: &str) -> Option<&'v mut SerdeValue> {
+    match value {
+        SerdeValue::Object(fields) => fields.get_mut(segment),
+        SerdeValue::Array(items) => segment.parse::<usize>().ok().and_then(|index| items.get_mut(index)),
+        _ => None,
+    }
+}
+
+pub fn get_path<'v>(value: &'v SerdeValue, path: &str) -> Option<&'v SerdeValue> {
+    path_segments(path).into_iter().try_fold(value, child)
+}
+
+// Missing objects along the way are created; stepping into anything but an
+// object or (in range) list is an error.
+pub fn set_path(value: &mut SerdeValue, path: &str, new_value: SerdeValue) -> Result<(), String> {
+    let segments = path_segments(path);
+    let Some((last, parents)) = segments.split_last() else {
+        return Err("Path cannot be empty".to_string());
+    };
+    let mut current = value;
+    for (depth, segment) in parents.iter().enumerate() {
+        if let SerdeValue::Object(fields) = current {
+            current = fields.entry(segment.to_string()).or_insert_with(|| SerdeValue::Object(Default::default()));
+        } else {
+            current = child_mut(current, segment)
+                .ok_or_else(|| format!("Cannot step into `{}` at `{}`", segment, segments[..depth].join(".")))?;
+        }
+    }
+    match current {
+        SerdeValue::Object(fields) => {
+            fields.insert(last.to_string(), new_value);
+            Ok(())
+        }
+        SerdeValue::Array(_) => match child_mut(current, last) {
+            Some(slot) => {
+                *slot = new_value;
+                Ok(())
+            }
+            None => Err(format!("Index `{}` is out of range at `{}`", last, parents.join("."))),
+        },
+        other => Err(format!("Cannot set `{}` on {}", last, other)),
+    }
+}
+
+// Returns the removed value, if there was one
+pub fn remove_path(value: &mut SerdeValue, path: &str) -> Option<SerdeValue> {
+    let segments = path_segments(path);
+    let (last, parents) = segments.split_last()?;
+    let parent = parents.iter().try_fold(value, |current, segment| child_mut(current, segment))?;
+    match parent {
+        SerdeValue::Object(fields) => fields.remove(*last),
+        SerdeValue::Array(items) => last.parse::<usize>().ok()
+            .filter(|&index| index < items.len())
+            .map(|index| items.remove(index)),
+        _ => None,
+    }
+}
+
+// Fields of `b` win; with `deep`, fields that are objects on both sides are merged recursively
+fn merge_objects(a: &mut serde_json::Map<String, SerdeValue>, b: serde_json::Map<String, SerdeValue>, deep: bool) {
+    for (key, value) in b {
+        match (a.get_mut(&key), value) {
+            (Some(SerdeValue::Object(inner)), SerdeValue::Object(other)) if deep => merge_objects(inner, other, deep),
+            (_, value) => {
+                a.insert(key, value);
+            }
+        }
+    }
+}
+
+// --- Number Helpers ---
+
+// Input `idx` (or its data slot) as a number, keeping integers apart from
+// floats. Anything else is an error rather than 0.
+fn num_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<Num, String> {
+    let value = inputs.request_or_data(idx, data)?;
+    inputs.numeric().read(&value).ok_or_else(|| format!("Expected a number, got {}", value))
+}
+
+fn number_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<f64, String> {
+    num_input(inputs, data, idx).map(Num::as_f64)
+}
+
+// Input `idx` (or its data slot) as a boolean; anything else is an error rather than false.
+fn bool_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<bool, String> {
+    let value = inputs.request_or_data(idx, data)?;
+    value.as_bool().ok_or_else(|| format!("Expected a boolean, got {}", value))
+}
+
+type UnaryMath = fn(f64) -> f64;
+type BinaryMath = fn(Num, Num) -> Num;
+
+// Deterministic value in [0, 1) for a seed (SplitMix64 over the seed's bits)
+pub fn seeded_random(seed: f64) -> f64 {
+    let mut z = seed.to_bits().wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// --- String Helpers ---
+
+// Longest string (in characters) `repeatStringNode` and `padStringNode` will build
+pub const MAX_STRING_LENGTH: usize = 10_000_000;
+
+// Input `idx` (or its data slot) as a string; null reads as ""
+fn string_input(inputs: &LogicInputs, data: &[SerdeValue], idx: usize) -> Result<String, String> {
+    match inputs.request_or_data(idx, data)? {
+        SerdeValue::String(text) => Ok(text),
+        SerdeValue::Null => Ok(String::new()),
+        other => Err(format!("Expected a string, got {}", other)),
+    }
+}
+
+// Text of a value when spliced into a string: strings as-is, null as "",
+// decimals and NaN / Infinity by their number text, the rest as JSON
+pub fn display_text(value: &SerdeValue) -> String {
+    match value {
+        SerdeValue::String(text) => text.clone(),
+        SerdeValue::Null => String::new(),
+        other => numeric::number_text(other).map_or_else(|| other.to_string(), str::to_string),
+    }
+}
+
+// Fills `{name}` placeholders in `template`. A name is looked up (as a path)
+// in each object value in turn; a whole number `{0}` is the value connected
+// at that position. `{{` and `}}` are literal braces.
+pub fn format_template(template: &str, values: &[SerdeValue]) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err("Unclosed `{` in template (write `{{` for a literal brace)".to_string()),
+                    }
+                }
+                let name = name.trim();
+                let value = match name.parse::<usize>() {
+                    Ok(position) => values.get(position),
+                    Err(_) => values.iter().filter(|value| value.is_object()).find_map(|object| get_path(object, name)),
+                };
+                match value {
+                    Some(value) => out.push_str(&display_text(value)),
+                    None => return Err(format!("No value for placeholder {{{}}}", name)),
+                }
+            }
+            '}' => return Err("Unmatched `}` in template (write `}}` for a literal brace)".to_string()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+// Looks up an input or output socket definition of a registered node type
+pub fn find_socket<'r>(registry: &'r HashMap<String, NodeDefinition>, node_type: &str, is_input: bool, index: usize) -> Option<&'r SocketDef> {
+    let def = registry.get(node_type)?;
+    let sockets = if is_input { &def.schema.io.inputs } else { &def.schema.io.outputs };
+    sockets.get(index)
+}
+
 // --- Registry ---
 
 pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
@@ -92,6 +602,11 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
         val_type: typ.to_string(),
         ui: SocketUI { ui_type: ui.to_string(), show_name },
         max_connections: max,
+        lazy: false,
+    };
+    let mk_lazy_socket = |name: &str, typ: &str, ui: &str, show_name: bool, max: usize| SocketDef {
+        lazy: true,
+        ..mk_socket(name, typ, ui, show_name, max)
     };
     
     let mk_data = |idx: usize, ui: &str, default: SerdeValue| DataDef {
@@ -100,11 +615,19 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
         ui: DataUI { ui_type: ui.to_string(), show_name: false, options: None },
         default_value: default,
     };
+    // Dropdown over `options`, for a data-only socket
+    let mk_select = |idx: usize, options: &[&str], default: &str| DataDef {
+        data_type: "plugin".to_string(),
+        input_index: idx,
+        ui: DataUI { ui_type: "select".to_string(), show_name: false, options: Some(options.iter().map(|o| o.to_string()).collect()) },
+        default_value: serde_json::json!(default),
+    };
 
     // --- Boolean ---
     reg.insert("booleanNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Boolean".to_string(),
+            version: 1,
             io: IO {
                 inputs: vec![mk_socket("value", "boolean", "none", false, 0)],
                 outputs: vec![mk_socket("value", "boolean", "show", false, infinity)],
@@ -113,7 +636,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|_, data| {
-            Ok(data.get(0).cloned().unwrap_or(serde_json::json!(false)))
+            Ok(vec![data.first().cloned().unwrap_or(serde_json::json!(false))])
         }),
     });
 
@@ -121,6 +644,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("numberNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Number".to_string(),
+            version: 1,
             io: IO {
                 inputs: vec![mk_socket("value", "number", "none", false, 0)],
                 outputs: vec![mk_socket("value", "number", "show", false, infinity)],
@@ -129,7 +653,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|_, data| {
-            Ok(data.get(0).cloned().unwrap_or(serde_json::json!(0)))
+            Ok(vec![data.first().cloned().unwrap_or(serde_json::json!(0))])
         }),
     });
 
@@ -137,6 +661,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("stringNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "String".to_string(),
+            version: 1,
             io: IO {
                 inputs: vec![mk_socket("value", "string", "none", false, 0)],
                 outputs: vec![mk_socket("value", "string", "show", false, infinity)],
@@ -145,7 +670,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|_, data| {
-            Ok(data.get(0).cloned().unwrap_or(serde_json::json!("")))
+            Ok(vec![data.first().cloned().unwrap_or(serde_json::json!(""))])
         }),
     });
 
@@ -153,6 +678,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("additionNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Add".to_string(),
+            version: 1,
             io: IO {
                  inputs: vec![
                      mk_socket("a", "any", "show", false, 1),
@@ -167,27 +693,25 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|inputs, data| {
-             let val_a = if !inputs.get(0).map(|v| v.is_empty()).unwrap_or(true) { inputs[0][0].clone() } else { data.get(0).cloned().unwrap_or(serde_json::json!(0)) };
-             let val_b = if !inputs.get(1).map(|v| v.is_empty()).unwrap_or(true) { inputs[1][0].clone() } else { data.get(1).cloned().unwrap_or(serde_json::json!(0)) };
-             
-             if val_a.is_string() || val_b.is_string() {
-                 let get_str = |v: &SerdeValue| -> String {
-                     if let Some(s) = v.as_str() { s.to_string() }
-                     else { v.to_string() }
-                 };
-                 Ok(serde_json::json!(format!("{}{}", get_str(&val_a), get_str(&val_b))))
-             } else {
-                 let f_a = val_a.as_f64().unwrap_or(0.0);
-                 let f_b = val_b.as_f64().unwrap_or(0.0);
-                 Ok(serde_json::json!(f_a + f_b))
+             let val_a = inputs.request_or_data(0, data)?;
+             let val_b = inputs.request_or_data(1, data)?;
+
+             // Two numbers add, a string on either side concatenates, anything else is an error
+             match (inputs.numeric().read(&val_a), inputs.numeric().read(&val_b)) {
+                 (Some(a), Some(b)) => Ok(vec![inputs.numeric().finish(numeric::add(a, b), "Addition")?]),
+                 _ if val_a.is_string() || val_b.is_string() => {
+                     Ok(vec![serde_json::json!(format!("{}{}", display_text(&val_a), display_text(&val_b)))])
+                 }
+                 _ => Err(format!("Cannot add {} and {}", val_a, val_b)),
              }
         }),
     });
 
     // --- Subtraction ---
-    reg.insert("subractionNode".to_string(), NodeDefinition {
+    reg.insert("subtractionNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Subtract".to_string(),
+            version: 1,
             io: IO {
                  inputs: vec![
                      mk_socket("a", "number", "show", false, 1),
@@ -202,14 +726,8 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|inputs, data| {
-            let get_num = |idx: usize| -> f64 {
-                if idx < inputs.len() && !inputs[idx].is_empty() {
-                    inputs[idx][0].as_f64().unwrap_or(0.0)
-                } else {
-                    data.get(idx).and_then(|v| v.as_f64()).unwrap_or(0.0)
-                }
-            };
-            Ok(serde_json::json!(get_num(0) - get_num(1)))
+            let result = numeric::subtract(num_input(inputs, data, 0)?, num_input(inputs, data, 1)?);
+            Ok(vec![inputs.numeric().finish(result, "Subtraction")?])
         }),
     });
 
@@ -217,6 +735,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("multiplicationNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Multiply".to_string(),
+            version: 1,
              io: IO {
                  inputs: vec![
                      mk_socket("a", "number", "show", false, 1),
@@ -231,14 +750,8 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|inputs, data| {
-            let get_num = |idx: usize| -> f64 {
-                if idx < inputs.len() && !inputs[idx].is_empty() {
-                    inputs[idx][0].as_f64().unwrap_or(0.0)
-                } else {
-                    data.get(idx).and_then(|v| v.as_f64()).unwrap_or(0.0)
-                }
-            };
-            Ok(serde_json::json!(get_num(0) * get_num(1)))
+            let result = numeric::multiply(num_input(inputs, data, 0)?, num_input(inputs, data, 1)?);
+            Ok(vec![inputs.numeric().finish(result, "Multiplication")?])
         }),
     });
 
@@ -246,6 +759,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("divisionNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Divide".to_string(),
+            version: 1,
              io: IO {
                  inputs: vec![
                      mk_socket("a", "number", "show", false, 1),
@@ -260,16 +774,41 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|inputs, data| {
-            let get_num = |idx: usize| -> f64 {
-                if idx < inputs.len() && !inputs[idx].is_empty() {
-                    inputs[idx][0].as_f64().unwrap_or(0.0)
-                } else {
-                    data.get(idx).and_then(|v| v.as_f64()).unwrap_or(0.0)
-                }
-            };
-            let a = get_num(0);
-            let b = get_num(1);
-            if b == 0.0 { Ok(serde_json::json!(0.0)) } else { Ok(serde_json::json!(a / b)) }
+            // Division by zero is up to the numeric policy
+            let (a, b) = (num_input(inputs, data, 0)?, num_input(inputs, data, 1)?);
+            let operation = if b.is_zero() { "Division by zero" } else { "Division" };
+            Ok(vec![inputs.numeric().finish(numeric::divide(a, b), operation)?])
+        }),
+    });
+
+    // --- Divide with Remainder ---
+    reg.insert("divModNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Divide with Remainder".to_string(),
+            version: 1,
+             io: IO {
+                 inputs: vec![
+                     mk_socket("a", "number", "show", false, 1),
+                     mk_socket("b", "number", "show", false, 1),
+                 ],
+                 outputs: vec![
+                     mk_socket("quotient", "number", "show", true, infinity),
+                     mk_socket("remainder", "number", "show", true, infinity),
+                 ],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(0)),
+                mk_data(1, "input", serde_json::json!(0)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+            // Euclidean: the remainder is never negative; integers stay integers
+            let (a, b) = (num_input(inputs, data, 0)?, num_input(inputs, data, 1)?);
+            let (quotient, remainder) = numeric::div_mod(a, b);
+            let operation = if b.is_zero() { "Division by zero" } else { "Division" };
+            let policy = inputs.numeric();
+            Ok(vec![policy.finish(quotient, operation)?, policy.finish(remainder, operation)?])
         }),
     });
 
@@ -277,23 +816,35 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("comparisonNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Compare".to_string(),
+            version: 2,
              io: IO {
                  inputs: vec![
                      mk_socket("a", "any", "show", false, 1),
                      mk_socket("b", "any", "show", false, 1),
+                     mk_socket("operator", "string", "none", false, 0),
+                     mk_socket("tolerance", "number", "show", true, 1),
                  ],
-                 outputs: vec![mk_socket("isEqual", "boolean", "show", false, infinity)],
+                 outputs: vec![mk_socket("result", "boolean", "show", false, infinity)],
             },
             data: vec![
                 mk_data(0, "input", serde_json::json!(0)),
                 mk_data(1, "input", serde_json::json!(0)),
+                mk_select(2, COMPARISON_OPERATORS, "=="),
+                mk_data(3, "input", serde_json::json!(DEFAULT_TOLERANCE)),
             ],
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|inputs, data| {
-             let val_a = if !inputs.get(0).map(|v| v.is_empty()).unwrap_or(true) { inputs[0][0].clone() } else { data.get(0).cloned().unwrap_or(serde_json::json!(0)) };
-             let val_b = if !inputs.get(1).map(|v| v.is_empty()).unwrap_or(true) { inputs[1][0].clone() } else { data.get(1).cloned().unwrap_or(serde_json::json!(0)) };
-             Ok(serde_json::json!(val_a == val_b))
+             // See `compare_with` for how values of different types compare
+             let val_a = inputs.request_or_data(0, data)?;
+             let val_b = inputs.request_or_data(1, data)?;
+             let operator = data.get(2).and_then(|op| op.as_str()).unwrap_or("==");
+             let tolerance = match inputs.request_or_data(3, data)? {
+                 SerdeValue::Null => DEFAULT_TOLERANCE,
+                 value => as_number(&value).filter(|t| *t >= 0.0)
+                     .ok_or_else(|| format!("Tolerance must be a non-negative number, got {}", value))?,
+             };
+             Ok(vec![serde_json::json!(compare_with(operator, &val_a, &val_b, tolerance)?)])
         }),
     });
 
@@ -301,11 +852,12 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("ifNode".to_string(), NodeDefinition {
          schema: NodeSchema {
             name: "If".to_string(),
+            version: 1,
              io: IO {
                  inputs: vec![
                      mk_socket("condition", "boolean", "show", false, 1),
-                     mk_socket("trueValue", "any", "show", false, 1),
-                     mk_socket("falseValue", "any", "show", false, 1),
+                     mk_lazy_socket("trueValue", "any", "show", false, 1),
+                     mk_lazy_socket("falseValue", "any", "show", false, 1),
                  ],
                  outputs: vec![mk_socket("output", "any", "show", false, infinity)],
             },
@@ -317,25 +869,107 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: false,
         },
         logic: Box::new(|inputs, data| {
-             let cond = if !inputs.get(0).map(|v| v.is_empty()).unwrap_or(true) { 
-                 inputs[0][0].as_bool().unwrap_or(false) 
-             } else { 
-                 data.get(0).and_then(|v| v.as_bool()).unwrap_or(false) 
-             };
+             let cond = bool_input(inputs, data, 0)?;
 
-             let val_true = if !inputs.get(1).map(|v| v.is_empty()).unwrap_or(true) { 
-                 inputs[1].get(0).unwrap_or(&SerdeValue::Null)
-             } else { 
-                 data.get(1).unwrap_or(&SerdeValue::Null)
-             };
+             // Only the branch that is taken gets evaluated
+             let branch = if cond { 1 } else { 2 };
+             Ok(vec![inputs.request_or_data(branch, data)?])
+        }),
+    });
 
-             let val_false = if !inputs.get(2).map(|v| v.is_empty()).unwrap_or(true) { 
-                 inputs[2].get(0).unwrap_or(&SerdeValue::Null)
-             } else { 
-                 data.get(2).unwrap_or(&SerdeValue::Null) 
-             };
+    // --- And ---
+    reg.insert("andNode".to_string(), NodeDefinition {
+         schema: NodeSchema {
+            name: "And".to_string(),
+            version: 1,
+             io: IO {
+                 inputs: vec![
+                     mk_socket("a", "boolean", "show", false, 1),
+                     mk_lazy_socket("b", "boolean", "show", false, 1),
+                 ],
+                 outputs: vec![mk_socket("result", "boolean", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(false)),
+                mk_data(1, "input", serde_json::json!(false)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let a = bool_input(inputs, data, 0)?;
+             // Short-circuit: `b` is only evaluated when `a` is true
+             let result = a && bool_input(inputs, data, 1)?;
+             Ok(vec![serde_json::json!(result)])
+        }),
+    });
+
+    // --- Or ---
+    reg.insert("orNode".to_string(), NodeDefinition {
+         schema: NodeSchema {
+            name: "Or".to_string(),
+            version: 1,
+             io: IO {
+                 inputs: vec![
+                     mk_socket("a", "boolean", "show", false, 1),
+                     mk_lazy_socket("b", "boolean", "show", false, 1),
+                 ],
+                 outputs: vec![mk_socket("result", "boolean", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(false)),
+                mk_data(1, "input", serde_json::json!(false)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let a = bool_input(inputs, data, 0)?;
+             // Short-circuit: `b` is only evaluated when `a` is false
+             let result = a || bool_input(inputs, data, 1)?;
+             Ok(vec![serde_json::json!(result)])
+        }),
+    });
+
+    // --- Not ---
+    reg.insert("notNode".to_string(), NodeDefinition {
+         schema: NodeSchema {
+            name: "Not".to_string(),
+            version: 1,
+             io: IO {
+                 inputs: vec![mk_socket("a", "boolean", "show", false, 1)],
+                 outputs: vec![mk_socket("result", "boolean", "show", false, infinity)],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!(false))],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let a = bool_input(inputs, data, 0)?;
+             Ok(vec![serde_json::json!(!a)])
+        }),
+    });
 
-             Ok(if cond { val_true.clone() } else { val_false.clone() })
+    // --- Xor ---
+    reg.insert("xorNode".to_string(), NodeDefinition {
+         schema: NodeSchema {
+            name: "Xor".to_string(),
+            version: 1,
+             io: IO {
+                 inputs: vec![
+                     mk_socket("a", "boolean", "show", false, 1),
+                     mk_socket("b", "boolean", "show", false, 1),
+                 ],
+                 outputs: vec![mk_socket("result", "boolean", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(false)),
+                mk_data(1, "input", serde_json::json!(false)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Needs both sides, so no lazy input here
+             let a = bool_input(inputs, data, 0)?;
+             let b = bool_input(inputs, data, 1)?;
+             Ok(vec![serde_json::json!(a != b)])
         }),
     });
 
@@ -343,6 +977,7 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
     reg.insert("outputNode".to_string(), NodeDefinition {
         schema: NodeSchema {
             name: "Output".to_string(),
+            version: 1,
             io: IO {
                 inputs: vec![mk_socket("input", "any", "none", false, 1)],
                 outputs: vec![],
@@ -351,14 +986,1275 @@ pub fn get_node_registry() -> HashMap<String, NodeDefinition> {
             auto_evaluate_on_connect: true, // Use Declarative Flag!
         },
         logic: Box::new(|inputs, _| {
-             if let Some(socket) = inputs.get(0) {
+             if let Some(socket) = inputs.first() {
                  if !socket.is_empty() {
-                     return Ok(socket[0].clone());
+                     return Ok(vec![socket[0].clone()]);
                  }
              }
-             Ok(SerdeValue::Null)
+             Ok(vec![SerdeValue::Null])
         }),
     });
 
-    reg
+    // --- Math ---
+    // Results that are not finite go through the engine's `NumericPolicy`
+    let unary_math: [(&str, &str, UnaryMath); 11] = [
+        ("sqrtNode", "Square Root", f64::sqrt),
+        ("sinNode", "Sine", f64::sin),
+        ("cosNode", "Cosine", f64::cos),
+        ("tanNode", "Tangent", f64::tan),
+        ("asinNode", "Arcsine", f64::asin),
+        ("acosNode", "Arccosine", f64::acos),
+        ("atanNode", "Arctangent", f64::atan),
+        ("expNode", "Exponential", f64::exp),
+        ("lnNode", "Natural Log", f64::ln),
+        ("log10Node", "Log 10", f64::log10),
+        ("log2Node", "Log 2", f64::log2),
+    ];
+    for (key, name, op) in unary_math {
+        reg.insert(key.to_string(), NodeDefinition {
+            schema: NodeSchema {
+                name: name.to_string(),
+                version: 1,
+                io: IO {
+                    // Angles are in radians
+                    inputs: vec![mk_socket("x", "number", "show", false, 1)],
+                    outputs: vec![mk_socket("result", "number", "show", false, infinity)],
+                },
+                data: vec![mk_data(0, "input", serde_json::json!(0))],
+                auto_evaluate_on_connect: false,
+            },
+            logic: Box::new(move |inputs, data| {
+                 let result = Num::Float(op(number_input(inputs, data, 0)?));
+                 Ok(vec![inputs.numeric().finish(result, name)?])
+            }),
+        });
+    }
+
+    reg.insert("absNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Absolute".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("x", "number", "show", false, 1)],
+                outputs: vec![mk_socket("result", "number", "show", false, infinity)],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!(0))],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let result = match num_input(inputs, data, 0)? {
+                 Num::Int(n) => n.checked_abs().map_or(Num::Float((n as f64).abs()), Num::Int),
+                 #[cfg(feature = "decimal")]
+                 Num::Decimal(d) => Num::Decimal(d.abs()),
+                 value => Num::Float(value.as_f64().abs()),
+             };
+             Ok(vec![inputs.numeric().finish(result, "Absolute")?])
+        }),
+    });
+
+    // Integer operands give an integer where the `Some` arm allows
+    let binary_math = [
+        ("powerNode", "Power", ["base", "exponent"], [2, 2],
+            (|a: Num, b: Num| a.combine(b, &numeric::POWER)) as BinaryMath),
+        // Sign follows `a`, like JS `%` (divModNode gives the non-negative, Euclidean one)
+        ("moduloNode", "Modulo", ["a", "b"], [0, 1], |a, b| a.combine(b, &numeric::REMAINDER)),
+        ("minNode", "Min", ["a", "b"], [0, 0], |a, b| a.combine(b, &numeric::MIN)),
+        ("maxNode", "Max", ["a", "b"], [0, 0], |a, b| a.combine(b, &numeric::MAX)),
+        ("atan2Node", "Arctangent 2", ["y", "x"], [0, 1], |a, b| Num::Float(a.as_f64().atan2(b.as_f64()))),
+        ("logNode", "Log", ["x", "base"], [1, 10], |a, b| Num::Float(a.as_f64().log(b.as_f64()))),
+    ];
+    for (key, name, [a, b], [default_a, default_b], op) in binary_math {
+        reg.insert(key.to_string(), NodeDefinition {
+            schema: NodeSchema {
+                name: name.to_string(),
+                version: 1,
+                io: IO {
+                    inputs: vec![
+                        mk_socket(a, "number", "show", true, 1),
+                        mk_socket(b, "number", "show", true, 1),
+                    ],
+                    outputs: vec![mk_socket("result", "number", "show", false, infinity)],
+                },
+                data: vec![
+                    mk_data(0, "input", serde_json::json!(default_a)),
+                    mk_data(1, "input", serde_json::json!(default_b)),
+                ],
+                auto_evaluate_on_connect: false,
+            },
+            logic: Box::new(move |inputs, data| {
+                 let result = op(num_input(inputs, data, 0)?, num_input(inputs, data, 1)?);
+                 Ok(vec![inputs.numeric().finish(result, name)?])
+            }),
+        });
+    }
+
+    let rounding = [
+        ("floorNode", "Floor", Rounding::Floor),
+        ("ceilNode", "Ceil", Rounding::Ceil),
+        // Halves round away from zero
+        ("roundNode", "Round", Rounding::Nearest),
+    ];
+    for (key, name, round) in rounding {
+        reg.insert(key.to_string(), NodeDefinition {
+            schema: NodeSchema {
+                name: name.to_string(),
+                version: 1,
+                io: IO {
+                    inputs: vec![
+                        mk_socket("x", "number", "show", false, 1),
+                        mk_socket("precision", "number", "show", true, 1),
+                    ],
+                    outputs: vec![mk_socket("result", "number", "show", false, infinity)],
+                },
+                data: vec![
+                    mk_data(0, "input", serde_json::json!(0)),
+                    mk_data(1, "input", serde_json::json!(0)), // Decimal places
+                ],
+                auto_evaluate_on_connect: false,
+            },
+            logic: Box::new(move |inputs, data| {
+                 let (value, precision) = (num_input(inputs, data, 0)?, number_input(inputs, data, 1)?);
+                 let precision = precision.trunc().clamp(-28.0, 28.0) as i32;
+                 Ok(vec![inputs.numeric().finish(value.round(precision, round), name)?])
+            }),
+        });
+    }
+
+    reg.insert("clampNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Clamp".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("x", "number", "show", false, 1),
+                    mk_socket("min", "number", "show", true, 1),
+                    mk_socket("max", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("result", "number", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(0)),
+                mk_data(1, "input", serde_json::json!(0)),
+                mk_data(2, "input", serde_json::json!(1)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let value = num_input(inputs, data, 0)?;
+             let (min, max) = (num_input(inputs, data, 1)?, num_input(inputs, data, 2)?);
+             if min.as_f64() > max.as_f64() || min.as_f64().is_nan() || max.as_f64().is_nan() {
+                 return Err(format!("Invalid clamp range {} to {}", min.to_value(), max.to_value()));
+             }
+             let result = value.combine(min, &numeric::MAX).combine(max, &numeric::MIN);
+             Ok(vec![inputs.numeric().finish(result, "Clamp")?])
+        }),
+    });
+
+    reg.insert("lerpNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Lerp".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("a", "number", "show", true, 1),
+                    mk_socket("b", "number", "show", true, 1),
+                    mk_socket("t", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("result", "number", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(0)),
+                mk_data(1, "input", serde_json::json!(1)),
+                mk_data(2, "input", serde_json::json!(0.5)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // `t` outside 0..1 extrapolates
+             let (a, b, t) = (num_input(inputs, data, 0)?, num_input(inputs, data, 1)?, num_input(inputs, data, 2)?);
+             let result = numeric::add(a, numeric::multiply(numeric::subtract(b, a), t));
+             Ok(vec![inputs.numeric().finish(result, "Lerp")?])
+        }),
+    });
+
+    for (key, name, value) in [("piNode", "Pi", std::f64::consts::PI), ("eNode", "E", std::f64::consts::E)] {
+        reg.insert(key.to_string(), NodeDefinition {
+            schema: NodeSchema {
+                name: name.to_string(),
+                version: 1,
+                io: IO {
+                    inputs: vec![],
+                    outputs: vec![mk_socket("value", "number", "show", false, infinity)],
+                },
+                data: vec![],
+                auto_evaluate_on_connect: false,
+            },
+            logic: Box::new(move |_, _| Ok(vec![serde_json::json!(value)])),
+        });
+    }
+
+    reg.insert("randomNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Random".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("seed", "number", "show", true, 1),
+                    mk_socket("min", "number", "show", true, 1),
+                    mk_socket("max", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("value", "number", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(0)),
+                mk_data(1, "input", serde_json::json!(0)),
+                mk_data(2, "input", serde_json::json!(1)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Same seed, same value: results stay cacheable and graphs reproducible
+             let seed = number_input(inputs, data, 0)?;
+             let (min, max) = (number_input(inputs, data, 1)?, number_input(inputs, data, 2)?);
+             Ok(vec![inputs.numeric().finish(Num::Float(min + (max - min) * seeded_random(seed)), "Random")?])
+        }),
+    });
+
+    // --- Lists ---
+    reg.insert("makeListNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Make List".to_string(),
+            version: 1,
+            io: IO {
+                // One element per connection, in connection order
+                inputs: vec![mk_socket("items", "any", "show", true, infinity)],
+                outputs: vec![mk_socket("list", "list", "show", false, infinity)],
+            },
+            data: vec![],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, _| {
+             Ok(vec![SerdeValue::Array(inputs.first().cloned().unwrap_or_default())])
+        }),
+    });
+
+    reg.insert("listLengthNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "List Length".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("list", "list", "show", false, 1)],
+                outputs: vec![mk_socket("length", "number", "show", false, infinity)],
+            },
+            data: vec![],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             Ok(vec![serde_json::json!(list_input(inputs, data, 0)?.len())])
+        }),
+    });
+
+    reg.insert("listGetNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Get Item".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("list", "list", "show", true, 1),
+                    mk_socket("index", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("item", "any", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!([])),
+                mk_data(1, "input", serde_json::json!(0)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let list = list_input(inputs, data, 0)?;
+             let index = index_input(inputs, data, 1)?.unwrap_or(0);
+             let resolved = resolve_index(index, list.len());
+             if resolved < 0 || resolved as usize >= list.len() {
+                 return Err(format!("Index {} is out of range for a list of length {}", index, list.len()));
+             }
+             Ok(vec![list[resolved as usize].clone()])
+        }),
+    });
+
+    reg.insert("listSliceNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Slice List".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("list", "list", "show", true, 1),
+                    mk_socket("start", "number", "show", true, 1),
+                    mk_socket("end", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("list", "list", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!([])),
+                mk_data(1, "input", serde_json::json!(0)),
+                mk_data(2, "input", SerdeValue::Null), // Up to the end
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let list = list_input(inputs, data, 0)?;
+             let len = list.len();
+             let clamp = |index: i64| resolve_index(index, len).clamp(0, len as i64) as usize;
+             let start = clamp(index_input(inputs, data, 1)?.unwrap_or(0));
+             let end = index_input(inputs, data, 2)?.map_or(len, clamp);
+             Ok(vec![SerdeValue::Array(if start < end { list[start..end].to_vec() } else { Vec::new() })])
+        }),
+    });
+
+    reg.insert("listConcatNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Concat Lists".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("a", "list", "show", false, 1),
+                    mk_socket("b", "list", "show", false, 1),
+                ],
+                outputs: vec![mk_socket("list", "list", "show", false, infinity)],
+            },
+            data: vec![],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let mut list = list_input(inputs, data, 0)?;
+             list.extend(list_input(inputs, data, 1)?);
+             Ok(vec![SerdeValue::Array(list)])
+        }),
+    });
+
+    reg.insert("listSortNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Sort List".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("list", "list", "show", false, 1),
+                    mk_socket("descending", "boolean", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("list", "list", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!([])),
+                mk_data(1, "input", serde_json::json!(false)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let mut list = list_input(inputs, data, 0)?;
+             // Stable, in the order of `compare_values`
+             list.sort_by(compare_values);
+             if bool_input(inputs, data, 1)? {
+                 list.reverse();
+             }
+             Ok(vec![SerdeValue::Array(list)])
+        }),
+    });
+
+    reg.insert("listUniqueNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Unique Items".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("list", "list", "show", false, 1)],
+                outputs: vec![mk_socket("list", "list", "show", false, infinity)],
+            },
+            data: vec![],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Keeps the first occurrence; equal as in `compare_values` (1 and 1.0 are duplicates)
+             let mut unique: Vec<SerdeValue> = Vec::new();
+             for item in list_input(inputs, data, 0)? {
+                 if !unique.iter().any(|seen| compare_values(seen, &item).is_eq()) {
+                     unique.push(item);
+                 }
+             }
+             Ok(vec![SerdeValue::Array(unique)])
+        }),
+    });
+
+    reg.insert("rangeNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Range".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("start", "number", "show", true, 1),
+                    mk_socket("end", "number", "show", true, 1),
+                    mk_socket("step", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("list", "list", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(0)),
+                mk_data(1, "input", serde_json::json!(10)),
+                mk_data(2, "input", serde_json::json!(1)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Like Python's range: `end` is excluded, a step of the wrong sign gives []
+             let (start, end, step) = (num_input(inputs, data, 0)?, num_input(inputs, data, 1)?, num_input(inputs, data, 2)?);
+             let (f_start, f_end, f_step) = (start.as_f64(), end.as_f64(), step.as_f64());
+             if f_step == 0.0 {
+                 return Err("Range step cannot be 0".to_string());
+             }
+             let count = ((f_end - f_start) / f_step).ceil().max(0.0);
+             if count > MAX_RANGE_LENGTH as f64 {
+                 return Err(format!("Range would have more than {} items", MAX_RANGE_LENGTH));
+             }
+             // Integers (or whole decimals) give integers; any float gives floats
+             let whole = |n: Num| match n {
+                 Num::Float(_) => None,
+                 other => other.as_whole(),
+             };
+             let items = if let (Some(start), Some(_), Some(step)) = (whole(start), whole(end), whole(step)) {
+                 // Few items can still be huge values: stop instead of overflowing
+                 (0..count as i64)
+                     .map(|i| i.checked_mul(step).and_then(|offset| start.checked_add(offset)).map(|item| serde_json::json!(item)))
+                     .collect::<Option<Vec<_>>>()
+                     .ok_or_else(|| "Range values do not fit in a 64-bit integer".to_string())?
+             } else {
+                 (0..count as usize).map(|i| serde_json::json!(f_start + i as f64 * f_step)).collect()
+             };
+             Ok(vec![SerdeValue::Array(items)])
+        }),
+    });
+
+    // A reference to a node type, for map / filter / reduce (see `functions`)
+    reg.insert(functions::FUNCTION_NODE.to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Function".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("nodeType", "string", "none", false, 0)],
+                outputs: vec![mk_socket("function", "function", "show", false, infinity)],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!(""))],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|_, data| {
+             match data.first().and_then(|name| name.as_str()) {
+                 Some(name) if !name.is_empty() => Ok(vec![functions::function_value(name)]),
+                 _ => Err("No node type selected".to_string()),
+             }
+        }),
+    });
+
+    // The evaluator applies the function itself (it needs the registry), so `logic` is never called
+    let higher_order_logic: fn() -> LogicFn = || Box::new(|_, _| Err("Needs the evaluator to apply its function".to_string()));
+    for (key, name, output, output_type) in [
+        (functions::LIST_MAP_NODE, "Map List", "list", "list"),
+        (functions::LIST_FILTER_NODE, "Filter List", "list", "list"),
+        (functions::LIST_REDUCE_NODE, "Reduce List", "result", "any"),
+    ] {
+        let mut inputs = vec![
+            mk_socket("list", "list", "show", false, 1),
+            mk_socket("function", "function", "show", false, 1),
+        ];
+        let mut data = vec![mk_data(0, "none", serde_json::json!([]))];
+        if key == functions::LIST_REDUCE_NODE {
+            inputs.push(mk_socket("initial", "any", "show", true, 1));
+            data.push(mk_data(1, "none", SerdeValue::Null));
+            data.push(mk_data(2, "input", serde_json::json!(0)));
+        }
+        reg.insert(key.to_string(), NodeDefinition {
+            schema: NodeSchema {
+                name: name.to_string(),
+                version: 1,
+                io: IO {
+                    inputs,
+                    outputs: vec![mk_socket(output, output_type, "show", false, infinity)],
+                },
+                data,
+                auto_evaluate_on_connect: false,
+            },
+            logic: higher_order_logic(),
+        });
+    }
+
+    // --- Objects ---
+    reg.insert("makeObjectNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Make Object".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    // Comma separated field names, one per `values` connection in order
+                    mk_socket("keys", "string", "none", true, 0),
+                    mk_socket("values", "any", "show", true, infinity),
+                ],
+                outputs: vec![mk_socket("object", "object", "show", false, infinity)],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!("a, b"))],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let keys = path_input(inputs, data, 0)?;
+             let keys: Vec<&str> = keys.split(',').map(str::trim).filter(|key| !key.is_empty()).collect();
+             let values = inputs.get(1).cloned().unwrap_or_default();
+             if values.len() > keys.len() {
+                 return Err(format!("{} value(s) connected for {} key(s)", values.len(), keys.len()));
+             }
+             // Keys without a connected value are null
+             let mut values = values.into_iter();
+             let object = keys.into_iter()
+                 .map(|key| (key.to_string(), values.next().unwrap_or(SerdeValue::Null)))
+                 .collect();
+             Ok(vec![SerdeValue::Object(object)])
+        }),
+    });
+
+    reg.insert("objectGetNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Get Field".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("object", "any", "show", false, 1),
+                    mk_socket("path", "string", "show", true, 1),
+                ],
+                outputs: vec![
+                    mk_socket("value", "any", "show", true, infinity),
+                    mk_socket("found", "boolean", "show", true, infinity),
+                ],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!({})),
+                mk_data(1, "input", serde_json::json!("")),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Missing fields give null rather than an error; `found` tells them apart
+             let object = inputs.request_or_data(0, data)?;
+             let path = path_input(inputs, data, 1)?;
+             let value = get_path(&object, &path);
+             Ok(vec![value.cloned().unwrap_or(SerdeValue::Null), SerdeValue::Bool(value.is_some())])
+        }),
+    });
+
+    reg.insert("objectSetNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Set Field".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("object", "object", "show", false, 1),
+                    mk_socket("path", "string", "show", true, 1),
+                    mk_socket("value", "any", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("object", "object", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!({})),
+                mk_data(1, "input", serde_json::json!("key")),
+                mk_data(2, "input", serde_json::json!("")),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let mut object = SerdeValue::Object(object_input(inputs, data, 0)?);
+             set_path(&mut object, &path_input(inputs, data, 1)?, inputs.request_or_data(2, data)?)?;
+             Ok(vec![object])
+        }),
+    });
+
+    reg.insert("objectRemoveNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Remove Field".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("object", "object", "show", false, 1),
+                    mk_socket("path", "string", "show", true, 1),
+                ],
+                outputs: vec![
+                    mk_socket("object", "object", "show", true, infinity),
+                    mk_socket("removed", "any", "show", true, infinity),
+                ],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!({})),
+                mk_data(1, "input", serde_json::json!("key")),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Removing a missing field is not an error
+             let mut object = SerdeValue::Object(object_input(inputs, data, 0)?);
+             let removed = remove_path(&mut object, &path_input(inputs, data, 1)?);
+             Ok(vec![object, removed.unwrap_or(SerdeValue::Null)])
+        }),
+    });
+
+    reg.insert("objectMergeNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Merge Objects".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("a", "object", "show", false, 1),
+                    mk_socket("b", "object", "show", false, 1),
+                    mk_socket("deep", "boolean", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("object", "object", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!({})),
+                mk_data(1, "none", serde_json::json!({})),
+                mk_data(2, "input", serde_json::json!(false)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let mut merged = object_input(inputs, data, 0)?;
+             let deep = bool_input(inputs, data, 2)?;
+             merge_objects(&mut merged, object_input(inputs, data, 1)?, deep);
+             Ok(vec![SerdeValue::Object(merged)])
+        }),
+    });
+
+    reg.insert("objectEntriesNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Keys & Values".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("object", "object", "show", false, 1)],
+                outputs: vec![
+                    mk_socket("keys", "list", "show", true, infinity),
+                    mk_socket("values", "list", "show", true, infinity),
+                ],
+            },
+            data: vec![],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Both in key order
+             let (keys, values) = object_input(inputs, data, 0)?.into_iter()
+                 .map(|(key, value)| (SerdeValue::String(key), value))
+                 .unzip();
+             Ok(vec![SerdeValue::Array(keys), SerdeValue::Array(values)])
+        }),
+    });
+
+    reg.insert("objectHasNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Has Field".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("object", "any", "show", false, 1),
+                    mk_socket("path", "string", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("result", "boolean", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!({})),
+                mk_data(1, "input", serde_json::json!("key")),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let object = inputs.request_or_data(0, data)?;
+             Ok(vec![SerdeValue::Bool(get_path(&object, &path_input(inputs, data, 1)?).is_some())])
+        }),
+    });
+
+    // --- Strings ---
+    // Lengths and positions count characters (Unicode scalar values), not bytes
+    reg.insert("stringLengthNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "String Length".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("string", "string", "show", false, 1)],
+                outputs: vec![mk_socket("length", "number", "show", false, infinity)],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!(""))],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             Ok(vec![serde_json::json!(string_input(inputs, data, 0)?.chars().count())])
+        }),
+    });
+
+    reg.insert("substringNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Substring".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("string", "string", "show", false, 1),
+                    mk_socket("start", "number", "show", true, 1),
+                    mk_socket("end", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("string", "string", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!("")),
+                mk_data(1, "input", serde_json::json!(0)),
+                mk_data(2, "input", SerdeValue::Null), // Up to the end
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Same index rules as `listSliceNode`
+             let chars: Vec<char> = string_input(inputs, data, 0)?.chars().collect();
+             let len = chars.len();
+             let clamp = |index: i64| resolve_index(index, len).clamp(0, len as i64) as usize;
+             let start = clamp(index_input(inputs, data, 1)?.unwrap_or(0));
+             let end = index_input(inputs, data, 2)?.map_or(len, clamp);
+             let sub: String = if start < end { chars[start..end].iter().collect() } else { String::new() };
+             Ok(vec![SerdeValue::String(sub)])
+        }),
+    });
+
+    reg.insert("splitStringNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Split".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("string", "string", "show", false, 1),
+                    mk_socket("separator", "string", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("list", "list", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!("")),
+                mk_data(1, "input", serde_json::json!(",")),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let text = string_input(inputs, data, 0)?;
+             let separator = string_input(inputs, data, 1)?;
+             // An empty separator splits into characters
+             let parts: Vec<SerdeValue> = if separator.is_empty() {
+                 text.chars().map(|c| SerdeValue::String(c.to_string())).collect()
+             } else {
+                 text.split(separator.as_str()).map(|part| SerdeValue::String(part.to_string())).collect()
+             };
+             Ok(vec![SerdeValue::Array(parts)])
+        }),
+    });
+
+    reg.insert("joinStringNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Join".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("list", "list", "show", false, 1),
+                    mk_socket("separator", "string", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("string", "string", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!([])),
+                mk_data(1, "input", serde_json::json!(",")),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let parts: Vec<String> = list_input(inputs, data, 0)?.iter().map(display_text).collect();
+             Ok(vec![SerdeValue::String(parts.join(&string_input(inputs, data, 1)?))])
+        }),
+    });
+
+    reg.insert("replaceStringNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Replace".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("string", "string", "show", false, 1),
+                    mk_socket("find", "string", "show", true, 1),
+                    mk_socket("replacement", "string", "show", true, 1),
+                    mk_socket("all", "boolean", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("string", "string", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!("")),
+                mk_data(1, "input", serde_json::json!("")),
+                mk_data(2, "input", serde_json::json!("")),
+                mk_data(3, "input", serde_json::json!(true)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let text = string_input(inputs, data, 0)?;
+             let find = string_input(inputs, data, 1)?;
+             let replacement = string_input(inputs, data, 2)?;
+             // Nothing to find leaves the string as it is
+             if find.is_empty() {
+                 return Ok(vec![SerdeValue::String(text)]);
+             }
+             let replaced = if inputs.request_or_data(3, data)?.as_bool().unwrap_or(true) {
+                 text.replace(&find, &replacement)
+             } else {
+                 text.replacen(&find, &replacement, 1)
+             };
+             Ok(vec![SerdeValue::String(replaced)])
+        }),
+    });
+
+    for (key, name, transform) in [
+        ("trimStringNode", "Trim", (|text: &str| text.trim().to_string()) as fn(&str) -> String),
+        ("upperCaseNode", "Upper Case", str::to_uppercase),
+        ("lowerCaseNode", "Lower Case", str::to_lowercase),
+    ] {
+        reg.insert(key.to_string(), NodeDefinition {
+            schema: NodeSchema {
+                name: name.to_string(),
+                version: 1,
+                io: IO {
+                    inputs: vec![mk_socket("string", "string", "show", false, 1)],
+                    outputs: vec![mk_socket("string", "string", "show", false, infinity)],
+                },
+                data: vec![mk_data(0, "input", serde_json::json!(""))],
+                auto_evaluate_on_connect: false,
+            },
+            logic: Box::new(move |inputs, data| {
+                 Ok(vec![SerdeValue::String(transform(&string_input(inputs, data, 0)?))])
+            }),
+        });
+    }
+
+    for (key, name, test) in [
+        ("containsStringNode", "Contains", (|text: &str, search: &str| text.contains(search)) as fn(&str, &str) -> bool),
+        ("startsWithNode", "Starts With", |text, search| text.starts_with(search)),
+        ("endsWithNode", "Ends With", |text, search| text.ends_with(search)),
+    ] {
+        reg.insert(key.to_string(), NodeDefinition {
+            schema: NodeSchema {
+                name: name.to_string(),
+                version: 1,
+                io: IO {
+                    inputs: vec![
+                        mk_socket("string", "string", "show", false, 1),
+                        mk_socket("search", "string", "show", true, 1),
+                    ],
+                    outputs: vec![mk_socket("result", "boolean", "show", false, infinity)],
+                },
+                data: vec![
+                    mk_data(0, "input", serde_json::json!("")),
+                    mk_data(1, "input", serde_json::json!("")),
+                ],
+                auto_evaluate_on_connect: false,
+            },
+            logic: Box::new(move |inputs, data| {
+                 Ok(vec![SerdeValue::Bool(test(&string_input(inputs, data, 0)?, &string_input(inputs, data, 1)?))])
+            }),
+        });
+    }
+
+    reg.insert("padStringNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Pad".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("string", "string", "show", false, 1),
+                    mk_socket("length", "number", "show", true, 1),
+                    mk_socket("fill", "string", "show", true, 1),
+                    mk_socket("at start", "boolean", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("string", "string", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!("")),
+                mk_data(1, "input", serde_json::json!(0)),
+                mk_data(2, "input", serde_json::json!(" ")),
+                mk_data(3, "input", serde_json::json!(true)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             // Like JS padStart / padEnd: the fill repeats and is cut to fit
+             let text = string_input(inputs, data, 0)?;
+             let length = index_input(inputs, data, 1)?.unwrap_or(0).max(0) as usize;
+             let fill = string_input(inputs, data, 2)?;
+             let current = text.chars().count();
+             if length > MAX_STRING_LENGTH {
+                 return Err(format!("Padded string would be longer than {} characters", MAX_STRING_LENGTH));
+             }
+             if length <= current || fill.is_empty() {
+                 return Ok(vec![SerdeValue::String(text)]);
+             }
+             let padding: String = fill.chars().cycle().take(length - current).collect();
+             let padded = if inputs.request_or_data(3, data)?.as_bool().unwrap_or(true) { padding + &text } else { text + &padding };
+             Ok(vec![SerdeValue::String(padded)])
+        }),
+    });
+
+    reg.insert("repeatStringNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Repeat String".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("string", "string", "show", false, 1),
+                    mk_socket("count", "number", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("string", "string", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!("")),
+                mk_data(1, "input", serde_json::json!(2)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let text = string_input(inputs, data, 0)?;
+             let count = index_input(inputs, data, 1)?.unwrap_or(0);
+             if count < 0 {
+                 return Err(format!("Repeat count must be non-negative, got {}", count));
+             }
+             if text.chars().count().saturating_mul(count as usize) > MAX_STRING_LENGTH {
+                 return Err(format!("Repeated string would be longer than {} characters", MAX_STRING_LENGTH));
+             }
+             Ok(vec![SerdeValue::String(text.repeat(count as usize))])
+        }),
+    });
+
+    reg.insert("formatStringNode".to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Format".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("template", "string", "show", true, 1),
+                    // Objects (from e.g. makeObjectNode) supply `{name}`, any value `{0}`, `{1}`...
+                    mk_socket("values", "any", "show", true, infinity),
+                ],
+                outputs: vec![mk_socket("string", "string", "show", false, infinity)],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!("Hello, {name}!"))],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, data| {
+             let values = inputs.get(1).cloned().unwrap_or_default();
+             Ok(vec![SerdeValue::String(format_template(&string_input(inputs, data, 0)?, &values)?)])
+        }),
+    });
+
+    // --- Groups ---
+    // Sockets of a group node come from the interface nodes in its inner graph
+    // (see `group::GroupGraph`), and the evaluator runs that graph instead of `logic`.
+    reg.insert(group::GROUP_NODE.to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Group".to_string(),
+            version: 1,
+            io: IO { inputs: vec![], outputs: vec![] },
+            data: vec![],
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|_, _| Err("Group node has no inner graph".to_string())),
+    });
+
+    // Inside a group: the value fed into the matching group input socket
+    reg.insert(group::GROUP_INPUT_NODE.to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Group Input".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![],
+                outputs: vec![mk_socket("value", "any", "show", false, infinity)],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!(""))], // Socket name
+            auto_evaluate_on_connect: false,
+        },
+        // Only reached when the inner graph is evaluated on its own (while editing
+        // it) and nothing is connected to the group's socket outside
+        logic: Box::new(|_, _| Ok(vec![SerdeValue::Null])),
+    });
+
+    reg.insert(group::GROUP_OUTPUT_NODE.to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Group Output".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("value", "any", "none", false, 1)],
+                outputs: vec![],
+            },
+            data: vec![mk_data(0, "input", serde_json::json!(""))], // Socket name
+            auto_evaluate_on_connect: false,
+        },
+        logic: Box::new(|inputs, _| {
+             Ok(vec![inputs.first().and_then(|socket| socket.first()).cloned().unwrap_or(SerdeValue::Null)])
+        }),
+    });
+
+    // --- Iteration ---
+    // The evaluator runs the body graph kept in the node (see `iteration`) instead of `logic`
+    let loop_logic: fn() -> LogicFn = || Box::new(|_, _| Err("Loop node has no body graph".to_string()));
+
+    reg.insert(iteration::REPEAT_NODE.to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Repeat".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("count", "number", "show", true, 1),
+                    mk_socket("initial", "any", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("value", "any", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "input", serde_json::json!(1)),
+                mk_data(1, "input", serde_json::json!(0)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: loop_logic(),
+    });
+
+    reg.insert(iteration::FOR_EACH_NODE.to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "For Each".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![mk_socket("list", "list", "show", true, 1)],
+                outputs: vec![mk_socket("results", "any", "show", false, infinity)],
+            },
+            data: vec![],
+            auto_evaluate_on_connect: false,
+        },
+        logic: loop_logic(),
+    });
+
+    reg.insert(iteration::FOLD_NODE.to_string(), NodeDefinition {
+        schema: NodeSchema {
+            name: "Fold".to_string(),
+            version: 1,
+            io: IO {
+                inputs: vec![
+                    mk_socket("list", "list", "show", true, 1),
+                    mk_socket("initial", "any", "show", true, 1),
+                ],
+                outputs: vec![mk_socket("accumulator", "any", "show", false, infinity)],
+            },
+            data: vec![
+                mk_data(0, "none", serde_json::json!([])),
+                mk_data(1, "input", serde_json::json!(0)),
+            ],
+            auto_evaluate_on_connect: false,
+        },
+        logic: loop_logic(),
+    });
+
+    reg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::GraphEngine;
+    use crate::test_util::{add_with, connect, error, run, value};
+    use serde_json::json;
+
+    fn first(node_type: &str, data: Vec<SerdeValue>) -> SerdeValue {
+        run(node_type, data).unwrap().remove(0)
+    }
+
+    fn fails(node_type: &str, data: Vec<SerdeValue>) -> String {
+        run(node_type, data).unwrap_err().message
+    }
+
+    #[test]
+    fn coercions_follow_the_table() {
+        assert!(check_type_compatibility("number", "string").is_ok());
+        assert!(check_type_compatibility("string", "number").is_err());
+        assert!(check_type_compatibility("list", "any").is_ok());
+        assert_eq!(coerce_value(json!(1.5), "string"), json!("1.5"));
+        assert_eq!(coerce_value(json!(true), "string"), json!("true"));
+        assert_eq!(coerce_value(json!([1]), "string"), json!([1]));
+        // Numbers carried as objects turn into their text, not JSON
+        assert_eq!(coerce_value(json!({ "decimal": "0.30", "display": 0.3 }), "string"), json!("0.30"));
+        assert_eq!(coerce_value(numeric::number_value(f64::NEG_INFINITY), "string"), json!("-Infinity"));
+        assert_eq!(display_text(&numeric::number_value(f64::NAN)), "NaN");
+    }
+
+    #[test]
+    fn value_types() {
+        assert_eq!(value_type(&json!(1)), "number");
+        assert_eq!(value_type(&json!("a")), "string");
+        assert_eq!(value_type(&json!([1])), "list");
+        assert_eq!(value_type(&json!({ "a": 1 })), "object");
+        assert_eq!(value_type(&json!({ "function": "sqrtNode" })), "function");
+        assert_eq!(value_type(&SerdeValue::Null), "any");
+        // Only the tagged form is a number; the plain strings stay strings
+        assert_eq!(value_type(&numeric::number_value(f64::NAN)), "number");
+        assert_eq!(value_type(&json!({ "decimal": "1", "display": 1.0 })), "number");
+        assert_eq!(value_type(&json!("NaN")), "string");
+        assert_eq!(value_type(&json!({ "number": "many" })), "object");
+    }
+
+    #[test]
+    fn list_nodes() {
+        let mut engine = GraphEngine::new();
+        for (id, v) in [("x", 3), ("y", 1), ("z", 3)] {
+            add_with(&mut engine, id, "numberNode", vec![json!(v)]);
+        }
+        add_with(&mut engine, "list", "makeListNode", vec![]);
+        for id in ["x", "y", "z"] {
+            connect(&mut engine, id, 0, "list", 0);
+        }
+        assert_eq!(value(&mut engine, "list"), json!([3, 1, 3]));
+
+        assert_eq!(first("listLengthNode", vec![json!([1, 2])]), json!(2));
+        assert_eq!(first("listGetNode", vec![json!([1, 2, 3]), json!(-1)]), json!(3));
+        assert!(fails("listGetNode", vec![json!([1]), json!(5)]).contains("out of range"));
+        assert_eq!(first("listSliceNode", vec![json!([1, 2, 3, 4]), json!(1), json!(-1)]), json!([2, 3]));
+        assert_eq!(first("listSortNode", vec![json!([3, "b", 1, "a"]), json!(false)]), json!([1, 3, "a", "b"]));
+        assert_eq!(first("listSortNode", vec![json!([1, 3, 2]), json!(true)]), json!([3, 2, 1]));
+        // NaN sorts after every other number instead of breaking the order
+        let nan = numeric::number_value(f64::NAN);
+        let mut with_nan = vec![json!(3), nan.clone(), json!(1), nan.clone(), json!(2)];
+        with_nan.extend((0..20).map(|i| json!(i)));
+        let sorted = first("listSortNode", vec![SerdeValue::Array(with_nan), json!(false)]);
+        assert_eq!(sorted.as_array().unwrap()[23..], [nan.clone(), nan.clone()]);
+        assert_eq!(compare_values(&nan, &json!(1)), Ordering::Greater);
+        assert_eq!(compare_values(&nan, &nan), Ordering::Equal);
+        assert_eq!(first("listUniqueNode", vec![json!([1, 1.0, 2, 1])]), json!([1, 2]));
+        assert_eq!(first("rangeNode", vec![json!(5), json!(0), json!(-2)]), json!([5, 3, 1]));
+        assert!(fails("rangeNode", vec![json!(0), json!(1), json!(0)]).contains("step"));
+        let huge = vec![json!(-9_000_000_000_000_000_000i64), json!(9_200_000_000_000_000_000i64), json!(9_000_000_000_000_000_000i64)];
+        assert!(fails("rangeNode", huge).contains("64-bit"));
+        // Indices must be whole numbers, not cut down
+        assert_eq!(first("listGetNode", vec![json!([1, 2, 3]), json!(1.0)]), json!(2));
+        assert!(fails("listGetNode", vec![json!([1, 2, 3]), json!(1.9)]).contains("whole number"));
+        assert!(fails("listSliceNode", vec![json!([1, 2]), json!(0), json!(1e300)]).contains("whole number"));
+    }
+
+    #[test]
+    fn object_nodes() {
+        let object = json!({ "a": { "b": [10, 20] }, "c": 1 });
+        assert_eq!(run("objectGetNode", vec![object.clone(), json!("a.b.1")]).unwrap(), [json!(20), json!(true)]);
+        assert_eq!(run("objectGetNode", vec![object.clone(), json!("a.x")]).unwrap(), [SerdeValue::Null, json!(false)]);
+        assert_eq!(first("objectSetNode", vec![json!({}), json!("a.b"), json!(1)]), json!({ "a": { "b": 1 } }));
+        assert_eq!(run("objectRemoveNode", vec![object.clone(), json!("c")]).unwrap(), [json!({ "a": { "b": [10, 20] } }), json!(1)]);
+        assert_eq!(first("objectHasNode", vec![object.clone(), json!("a.b")]), json!(true));
+        assert_eq!(
+            first("objectMergeNode", vec![json!({ "a": { "x": 1 } }), json!({ "a": { "y": 2 } }), json!(true)]),
+            json!({ "a": { "x": 1, "y": 2 } })
+        );
+        assert_eq!(
+            first("objectMergeNode", vec![json!({ "a": { "x": 1 } }), json!({ "a": { "y": 2 } }), json!(false)]),
+            json!({ "a": { "y": 2 } })
+        );
+        assert_eq!(run("objectEntriesNode", vec![json!({ "b": 2, "a": 1 })]).unwrap(), [json!(["a", "b"]), json!([1, 2])]);
+    }
+
+    #[test]
+    fn string_nodes() {
+        assert_eq!(first("stringLengthNode", vec![json!("héllo")]), json!(5));
+        assert_eq!(first("substringNode", vec![json!("héllo"), json!(1), json!(-1)]), json!("éll"));
+        assert_eq!(first("splitStringNode", vec![json!("a,b,,c"), json!(",")]), json!(["a", "b", "", "c"]));
+        assert_eq!(first("joinStringNode", vec![json!(["a", 1, true]), json!("-")]), json!("a-1-true"));
+        assert_eq!(first("replaceStringNode", vec![json!("aXbXc"), json!("X"), json!("_"), json!(false)]), json!("a_bXc"));
+        assert_eq!(first("trimStringNode", vec![json!("  x ")]), json!("x"));
+        assert_eq!(first("upperCaseNode", vec![json!("ab")]), json!("AB"));
+        assert_eq!(first("containsStringNode", vec![json!("abc"), json!("b")]), json!(true));
+        assert_eq!(first("padStringNode", vec![json!("7"), json!(3), json!("0"), json!(true)]), json!("007"));
+        assert_eq!(first("repeatStringNode", vec![json!("ab"), json!(3)]), json!("ababab"));
+        assert!(fails("repeatStringNode", vec![json!("ab"), json!(2.7)]).contains("whole number"));
+    }
+
+    #[test]
+    fn format_string_fills_placeholders() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "object", "objectSetNode", vec![json!({ "a": { "b": [1] } }), json!("name"), json!("Ada")]);
+        add_with(&mut engine, "named", "formatStringNode", vec![json!("Hello, {name}! {a.b}")]);
+        connect(&mut engine, "object", 0, "named", 1);
+        assert_eq!(value(&mut engine, "named"), json!("Hello, Ada! [1]"));
+
+        add_with(&mut engine, "one", "numberNode", vec![json!(1)]);
+        add_with(&mut engine, "two", "numberNode", vec![json!(2)]);
+        add_with(&mut engine, "positional", "formatStringNode", vec![json!("{0}+{1} {{x}}")]);
+        connect(&mut engine, "one", 0, "positional", 1);
+        connect(&mut engine, "two", 0, "positional", 1);
+        assert_eq!(value(&mut engine, "positional"), json!("1+2 {x}"));
+
+        assert!(fails("formatStringNode", vec![json!("{name")]).contains("nclosed"));
+        assert!(fails("formatStringNode", vec![json!("{name}")]).contains("No value"));
+    }
+
+    #[test]
+    fn math_nodes() {
+        assert_eq!(first("sqrtNode", vec![json!(9)]), json!(3.0));
+        assert_eq!(first("absNode", vec![json!(-4)]), json!(4));
+        assert_eq!(first("powerNode", vec![json!(2), json!(10)]), json!(1024));
+        assert_eq!(first("moduloNode", vec![json!(-7), json!(3)]), json!(-1));
+        assert_eq!(first("minNode", vec![json!(2), json!(1.5)]), json!(1.5));
+        assert_eq!(first("roundNode", vec![json!(2.345), json!(2)]), json!(2.35));
+        assert_eq!(first("floorNode", vec![json!(1234.5), json!(-2)]), json!(1200));
+        assert_eq!(first("ceilNode", vec![json!(1.01), json!(0)]), json!(2));
+        assert_eq!(first("clampNode", vec![json!(5), json!(0), json!(1)]), json!(1));
+        assert_eq!(first("lerpNode", vec![json!(0), json!(10), json!(0.25)]), json!(2.5));
+        assert_eq!(first("piNode", vec![]), json!(std::f64::consts::PI));
+        assert_eq!(first("divModNode", vec![json!(-7), json!(2)]), json!(-4));
+    }
+
+    #[test]
+    fn random_is_seeded_and_in_range() {
+        let a = first("randomNode", vec![json!(42), json!(5), json!(10)]);
+        assert_eq!(a, first("randomNode", vec![json!(42), json!(5), json!(10)]));
+        assert_ne!(a, first("randomNode", vec![json!(43), json!(5), json!(10)]));
+        let a = a.as_f64().unwrap();
+        assert!((5.0..10.0).contains(&a));
+    }
+
+    #[test]
+    fn logic_nodes() {
+        assert_eq!(first("notNode", vec![json!(true)]), json!(false));
+        assert_eq!(first("xorNode", vec![json!(true), json!(true)]), json!(false));
+        assert_eq!(first("andNode", vec![json!(true), json!(true)]), json!(true));
+        assert_eq!(first("orNode", vec![json!(false), json!(false)]), json!(false));
+        assert!(run("andNode", vec![json!(true), json!("yes")]).is_err());
+        assert!(run("orNode", vec![json!(0), json!(true)]).is_err());
+        assert!(run("xorNode", vec![json!(true), SerdeValue::Null]).is_err());
+        assert!(run("notNode", vec![json!("false")]).is_err());
+        // Short-circuiting still skips the other side
+        assert_eq!(first("andNode", vec![json!(false), json!("yes")]), json!(false));
+
+        // A number reaching `if` through an "any" output fails instead of choosing "else"
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "sum", "additionNode", vec![json!(1), json!(2)]);
+        add_with(&mut engine, "if", "ifNode", vec![json!(false), json!("then"), json!("else")]);
+        connect(&mut engine, "sum", 0, "if", 0);
+        assert!(error(&mut engine, "if").message.contains("Expected a boolean"));
+        assert_eq!(first("ifNode", vec![json!(true), json!("then"), json!("else")]), json!("then"));
+
+        assert!(fails("listSortNode", vec![json!([2, 1]), json!("desc")]).contains("Expected a boolean"));
+        assert!(fails("objectMergeNode", vec![json!({}), json!({}), json!(1)]).contains("Expected a boolean"));
+    }
+
+    #[test]
+    fn comparison_operators_and_tolerance() {
+        let compare = |a: SerdeValue, b: SerdeValue, op: &str| first("comparisonNode", vec![a, b, json!(op), json!(DEFAULT_TOLERANCE)]);
+        assert_eq!(compare(json!(1), json!(1.0), "=="), json!(true));
+        assert_eq!(compare(json!(0.30000000000000004), json!(0.3), "=="), json!(true));
+        assert_eq!(compare(json!(2), json!(3), "<"), json!(true));
+        assert_eq!(compare(json!("b"), json!("a"), ">="), json!(true));
+        assert_eq!(compare(json!([1, 2]), json!([1, 2]), "=="), json!(true));
+        assert_eq!(compare(json!(1), json!("1"), "=="), json!(false));
+        assert_eq!(compare(json!(1), json!("1"), "!="), json!(true));
+        assert!(fails("comparisonNode", vec![json!(1), json!("1"), json!("<"), json!(0)]).contains("Cannot order"));
+        assert!(fails("comparisonNode", vec![json!(1), json!(1), json!("=<"), json!(0)]).contains("operator"));
+        assert_eq!(first("comparisonNode", vec![json!(1), json!(1.05), json!("=="), json!(0.1)]), json!(true));
+    }
 }
@@ -0,0 +1,228 @@
+use crate::definitions::NodeMigration;
+use crate::error::EngineError;
+use crate::graph::Edge;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as SerdeValue;
+use std::collections::HashMap;
+
+// --- Saved Graph Document ---
+
+// Bumped whenever the document layout changes incompatibly
+// v2: nodes record the schema version of their type
+pub const GRAPH_FORMAT_VERSION: u32 = 2;
+
+// A whole graph as saved to / loaded from JSON by `export_graph` / `import_graph`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GraphDocument {
+    pub version: u32,
+    pub nodes: Vec<DocumentNode>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub data: Vec<SerdeValue>,
+    // Schema version of `node_type` when saved (documents before v2 have none: 1)
+    #[serde(default = "default_schema_version")]
+    pub version: u32,
+    // Opaque frontend state (position, collapsed, ...), passed through untouched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui: Option<SerdeValue>,
+    // Inner graph of a group node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<Box<GroupDocument>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GroupDocument {
+    pub nodes: Vec<DocumentNode>,
+    pub edges: Vec<Edge>,
+    // Interface node IDs, in socket order
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+fn default_schema_version() -> u32 {
+    1
+}
+
+// What `migrate` changed, so the frontend can tell the user
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct MigrationReport {
+    pub nodes: Vec<MigratedNode>,
+    #[serde(rename = "droppedEdges")]
+    pub dropped_edges: Vec<Edge>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MigratedNode {
+    pub id: String,
+    #[serde(rename = "fromType")]
+    pub from_type: String,
+    #[serde(rename = "toType")]
+    pub to_type: String,
+    #[serde(rename = "fromVersion")]
+    pub from_version: u32,
+    #[serde(rename = "toVersion")]
+    pub to_version: u32,
+}
+
+impl GraphDocument {
+    /// Upgrades every node that has a matching migration, chaining them until
+    /// none applies, then remaps (or drops) edges whose socket indices moved.
+    /// Group inner graphs are migrated the same way.
+    pub fn migrate(&mut self, migrations: &[NodeMigration]) -> MigrationReport {
+        let mut report = MigrationReport::default();
+        migrate_graph(&mut self.nodes, &mut self.edges, migrations, &mut report);
+        report
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, EngineError> {
+        serde_json::from_str(json).map_err(|e| EngineError::Deserialization { message: e.to_string() })
+    }
+
+    pub fn to_json(&self) -> String {
+        // Only plain data in here, so serialization cannot fail
+        serde_json::to_string(self).expect("graph document is always serializable")
+    }
+}
+
+fn migrate_graph(nodes: &mut [DocumentNode], edges: &mut Vec<Edge>, migrations: &[NodeMigration], report: &mut MigrationReport) {
+    // Migration steps applied per node ID, in order, for remapping edges
+    let mut applied: HashMap<String, Vec<&NodeMigration>> = HashMap::new();
+
+    for node in nodes.iter_mut() {
+        if let Some(group) = &mut node.group {
+            migrate_graph(&mut group.nodes, &mut group.edges, migrations, report);
+        }
+        let (from_type, from_version) = (node.node_type.clone(), node.version);
+        let mut steps = Vec::new();
+        // Each step must rename or bump the version; the cap guards against
+        // a badly registered chain looping forever.
+        while steps.len() < migrations.len() {
+            let Some(step) = migrations.iter().find(|m| {
+                m.node_type == node.node_type
+                    && m.from_version == node.version
+                    && (m.to_version > m.from_version || m.rename_to.is_some())
+            }) else {
+                break;
+            };
+            if let Some(data_fn) = &step.data {
+                node.data = data_fn(std::mem::take(&mut node.data));
+            }
+            if let Some(new_type) = &step.rename_to {
+                node.node_type = new_type.clone();
+            }
+            node.version = step.to_version;
+            steps.push(step);
+        }
+
+        if !steps.is_empty() {
+            report.nodes.push(MigratedNode {
+                id: node.id.clone(),
+                from_type,
+                to_type: node.node_type.clone(),
+                from_version,
+                to_version: node.version,
+            });
+            applied.insert(node.id.clone(), steps);
+        }
+    }
+
+    if !applied.is_empty() {
+        for edge in std::mem::take(edges) {
+            let source_index = applied.get(&edge.source_id).map_or(Some(edge.source_output_index), |steps| {
+                steps.iter().try_fold(edge.source_output_index, |idx, step| step.map_output(idx))
+            });
+            let target_index = applied.get(&edge.target_id).map_or(Some(edge.target_input_index), |steps| {
+                steps.iter().try_fold(edge.target_input_index, |idx, step| step.map_input(idx))
+            });
+            match (source_index, target_index) {
+                (Some(source_output_index), Some(target_input_index)) => edges.push(Edge {
+                    source_output_index,
+                    target_input_index,
+                    ..edge
+                }),
+                _ => report.dropped_edges.push(edge),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::GraphEngine;
+    use crate::test_util::value;
+    use serde_json::json;
+
+    #[test]
+    fn old_type_keys_are_renamed_on_import() {
+        let doc = r#"{"version":1,"nodes":[
+            {"id":"a","type":"numberNode","data":[5]},
+            {"id":"sub","type":"subractionNode","data":[0,2]}],
+            "edges":[{"sourceId":"a","sourceOutputIndex":0,"targetId":"sub","targetInputIndex":0}]}"#;
+        let mut engine = GraphEngine::new();
+        let report = engine.import_graph(GraphDocument::from_json(doc).unwrap()).unwrap();
+        assert_eq!(report.nodes.len(), 1);
+        assert_eq!((report.nodes[0].from_type.as_str(), report.nodes[0].to_type.as_str()), ("subractionNode", "subtractionNode"));
+        assert_eq!(engine.node("sub").unwrap().node_type, "subtractionNode");
+        assert_eq!(value(&mut engine, "sub"), json!(3));
+    }
+
+    #[test]
+    fn migrations_chain_and_remap_sockets() {
+        let mut doc = GraphDocument::from_json(r#"{"version":2,"nodes":[
+            {"id":"a","type":"numberNode","data":[1]},
+            {"id":"old","type":"oldNode","data":[1,2],"version":1}],
+            "edges":[{"sourceId":"a","sourceOutputIndex":0,"targetId":"old","targetInputIndex":0},
+                     {"sourceId":"a","sourceOutputIndex":0,"targetId":"old","targetInputIndex":1}]}"#).unwrap();
+        let migrations = vec![
+            // v1 -> v2 swaps the inputs and drops the second data slot
+            NodeMigration {
+                node_type: "oldNode".into(),
+                from_version: 1,
+                to_version: 2,
+                rename_to: None,
+                inputs: Some(vec![Some(1), Some(0)]),
+                outputs: None,
+                data: Some(Box::new(|mut data| { data.truncate(1); data })),
+            },
+            // v2 -> v3 drops the (new) first input and renames the type
+            NodeMigration {
+                node_type: "oldNode".into(),
+                from_version: 2,
+                to_version: 3,
+                rename_to: Some("newNode".into()),
+                inputs: Some(vec![None, Some(0)]),
+                outputs: None,
+                data: None,
+            },
+        ];
+        let report = doc.migrate(&migrations);
+
+        let node = &doc.nodes[1];
+        assert_eq!((node.node_type.as_str(), node.version), ("newNode", 3));
+        assert_eq!(node.data, [json!(1)]);
+        // One entry per node, covering the whole chain
+        assert_eq!(report.nodes.len(), 1);
+        assert_eq!((report.nodes[0].from_version, report.nodes[0].to_version), (1, 3));
+        // Old input 0 became input 1, then was dropped; old input 1 ends up at 0
+        assert_eq!(doc.edges.len(), 1);
+        assert_eq!(doc.edges[0].target_input_index, 0);
+        assert_eq!(report.dropped_edges.len(), 1);
+    }
+
+    #[test]
+    fn comparison_nodes_gain_operator_and_tolerance() {
+        let doc = r#"{"version":2,"nodes":[{"id":"c","type":"comparisonNode","data":[1,1],"version":1}],"edges":[]}"#;
+        let mut engine = GraphEngine::new();
+        engine.import_graph(GraphDocument::from_json(doc).unwrap()).unwrap();
+        assert_eq!(engine.node("c").unwrap().data, [json!(1), json!(1), json!("=="), json!(1e-9)]);
+        assert_eq!(value(&mut engine, "c"), json!(true));
+        assert_eq!(engine.export_graph().nodes[0].version, 2);
+    }
+}
@@ -0,0 +1,1456 @@
+use crate::definitions::{self, NodeSchema};
+use crate::document::{DocumentNode, GraphDocument, GroupDocument, MigrationReport, GRAPH_FORMAT_VERSION};
+use crate::error::EngineError;
+use crate::evaluator::{Evaluator, NodeCache, NodeResult};
+use crate::graph::{self, ConnectionPolicy, Edge, EdgePosition, EdgeUpdate, InputConnection, Node, OutputConnection};
+use crate::group::{self, GroupGraph};
+use crate::iteration;
+use crate::numeric::{NumericPolicy, NumericSettings};
+use crate::history::{GraphOp, History, HistoryEntry, HistoryUpdate};
+use crate::validate::{self, RepairReport, ValidationReport};
+use serde_json::Value as SerdeValue;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// The graph model plus the node registry and value cache. This is the plain
+// Rust engine; `wasm::GraphEngine` wraps it for JS.
+pub struct GraphEngine {
+    nodes: HashMap<String, Node>,
+    cache: HashMap<String, NodeResult>, // Computed values per node, indexed by output socket
+    node_registry: HashMap<String, definitions::NodeDefinition>, // Stores logic + schema
+    migrations: Vec<definitions::NodeMigration>, // Upgrades for saved graphs
+    graph_types: HashMap<String, GroupGraph>, // Inner graphs of types from `register_graph_node_type`
+    iteration_limit: usize, // Per loop node evaluation
+    numeric: NumericSettings, // Policy for non-finite results, decimal mode
+    history: History,
+    // While a batch is applied: roots to invalidate once it is done
+    pending_invalidation: Option<Vec<String>>,
+    // Enclosing graphs while editing inside a group, innermost last
+    scopes: Vec<Scope>,
+}
+
+// A graph set aside by `enter_group`, restored by `exit_group`
+struct Scope {
+    group_id: String,
+    nodes: HashMap<String, Node>,
+    cache: HashMap<String, NodeResult>,
+    history: History,
+    // Inner graph as it was on entering, for the outer undo step
+    entered: HashMap<String, Node>,
+    // Value each group input node stands for: what is fed into its socket outside
+    input_preview: HashMap<String, SerdeValue>,
+}
+
+impl Default for GraphEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphEngine {
+    pub fn new() -> GraphEngine {
+        let registry = definitions::get_node_registry();
+        GraphEngine {
+            nodes: HashMap::new(),
+            cache: HashMap::new(),
+            node_registry: registry,
+            migrations: definitions::get_node_migrations(),
+            graph_types: HashMap::new(),
+            iteration_limit: iteration::DEFAULT_ITERATION_LIMIT,
+            numeric: NumericSettings::default(),
+            history: History::default(),
+            pending_invalidation: None,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn get_node_defs(&self) -> HashMap<String, NodeSchema> {
+        // Extract just the schema part (what the frontend needs)
+        let mut schemas = HashMap::new();
+        for (key, def) in &self.node_registry {
+            schemas.insert(key.clone(), def.schema.clone());
+        }
+        schemas
+    }
+
+    /// Registers the graph in `doc` as node type `name`. Its `groupInputNode`s and
+    /// `groupOutputNode`s (ordered by ID) become the sockets of the new type, which
+    /// then appears in `get_node_defs` and evaluates like a group node.
+    /// Returns the derived schema.
+    pub fn register_graph_node_type(&mut self, name: String, mut doc: GraphDocument) -> Result<NodeSchema, EngineError> {
+        if self.node_registry.contains_key(definitions::resolve_node_type(&name)) {
+            return Err(EngineError::TypeAlreadyRegistered { node_type: name });
+        }
+        if doc.version > GRAPH_FORMAT_VERSION {
+            return Err(EngineError::UnsupportedVersion { version: doc.version });
+        }
+        doc.migrate(&self.migrations);
+
+        let group = self.load_group(GroupDocument { nodes: doc.nodes, edges: doc.edges, inputs: Vec::new(), outputs: Vec::new() })?;
+        let schema = NodeSchema { name: name.clone(), ..group.schema() };
+        self.node_registry.insert(name.clone(), definitions::NodeDefinition {
+            schema: schema.clone(),
+            // Never called: the evaluator runs `graph_types[name]` instead
+            logic: Box::new(|_, _| Err("Graph node type has no inner graph".to_string())),
+        });
+        self.graph_types.insert(name, group);
+        Ok(schema)
+    }
+
+    /// Caps the iterations one repeat / for-each / fold node may run; evaluating
+    /// past it fails that node with `ITERATION_LIMIT`.
+    pub fn set_iteration_limit(&mut self, limit: usize) {
+        self.iteration_limit = limit;
+        // Results computed under the old limit may now be wrong either way
+        self.cache.clear();
+    }
+
+    /// Sets what math nodes do with results that are not finite numbers
+    /// (division by zero and the like), see `NumericPolicy`.
+    pub fn set_numeric_policy(&mut self, policy: NumericPolicy) {
+        self.numeric.policy = policy;
+        self.cache.clear();
+    }
+
+    /// Reads every number input as an exact decimal (see `numeric`), so that
+    /// e.g. 0.1 + 0.2 gives exactly 0.3.
+    #[cfg(feature = "decimal")]
+    pub fn set_decimal_mode(&mut self, enabled: bool) {
+        self.numeric.decimal = enabled;
+        self.cache.clear();
+    }
+
+    // Adds an upgrade step applied to saved graphs on `import_graph`
+    pub fn register_migration(&mut self, migration: definitions::NodeMigration) {
+        self.migrations.push(migration);
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.values()
+    }
+
+    pub fn add_node(&mut self, id: String, node_type: String, parsed_data: Vec<SerdeValue>) -> Result<(), EngineError> {
+        self.insert_node(id, node_type, parsed_data, None, None)
+    }
+
+    fn insert_node(&mut self, id: String, node_type: String, parsed_data: Vec<SerdeValue>, ui: Option<SerdeValue>, mut group: Option<Box<GroupGraph>>) -> Result<(), EngineError> {
+        if self.nodes.contains_key(&id) {
+            return Err(EngineError::DuplicateNodeId { node_id: id });
+        }
+
+        // Lookup node type in registry (deprecated keys map to their replacement)
+        let node_type = definitions::resolve_node_type(&node_type).to_string();
+        let def = self.node_registry.get(&node_type).ok_or_else(|| EngineError::UnknownNodeType {
+            node_type: node_type.clone(),
+            node_id: Some(id.clone()),
+        })?;
+        
+        // A group's sockets come from its interface instead of the registry
+        let (input_count, output_count) = match &group {
+            Some(group) if node_type == group::GROUP_NODE => (group.inputs.len(), group.outputs.len()),
+            _ => (def.schema.io.inputs.len(), def.schema.io.outputs.len()),
+        };
+        if group.is_none() {
+            group = iteration::default_body(&node_type).map(Box::new);
+        }
+
+        self.history.record(
+            vec![GraphOp::AddNode { id: id.clone(), node_type: node_type.clone(), data: parsed_data.clone(), ui: ui.clone(), group: group.clone() }],
+            vec![GraphOp::RemoveNode { id: id.clone() }],
+        );
+
+        let node = Node {
+            id: id.clone(),
+            node_type,
+            inputs: vec![Vec::new(); input_count],
+            outputs: vec![Vec::new(); output_count],
+            data: parsed_data,
+            ui,
+            group,
+        };
+
+        self.nodes.insert(id, node);
+        Ok(())
+    }
+
+    // Attaches frontend metadata (e.g. canvas position) that is saved with the graph
+    pub fn set_node_ui(&mut self, id: &str, ui: Option<SerdeValue>) -> Result<(), EngineError> {
+        let node = self.nodes.get_mut(id).ok_or_else(|| EngineError::NodeNotFound { node_id: id.to_string() })?;
+        node.ui = ui;
+        Ok(())
+    }
+
+    /// Snapshot of the whole graph as a versioned document. Nodes are sorted by
+    /// ID and edges listed per target input in connection order, so the output
+    /// is stable and re-importing preserves which edge is oldest on each socket.
+    pub fn export_graph(&self) -> GraphDocument {
+        let (nodes, edges) = self.document_graph(&self.nodes);
+        GraphDocument {
+            version: GRAPH_FORMAT_VERSION,
+            nodes,
+            edges,
+        }
+    }
+
+    fn document_graph(&self, graph_nodes: &HashMap<String, Node>) -> (Vec<DocumentNode>, Vec<Edge>) {
+        let mut ids: Vec<&String> = graph_nodes.keys().collect();
+        ids.sort();
+
+        let mut nodes = Vec::with_capacity(ids.len());
+        let mut edges = Vec::new();
+        for id in ids {
+            let node = &graph_nodes[id];
+            nodes.push(DocumentNode {
+                id: node.id.clone(),
+                node_type: node.node_type.clone(),
+                data: node.data.clone(),
+                version: self.node_registry.get(&node.node_type).map_or(1, |def| def.schema.version),
+                ui: node.ui.clone(),
+                group: node.group.as_ref().map(|group| {
+                    let (nodes, edges) = self.document_graph(&group.nodes);
+                    Box::new(GroupDocument {
+                        nodes,
+                        edges,
+                        inputs: group.inputs.clone(),
+                        outputs: group.outputs.clone(),
+                    })
+                }),
+            });
+            for (target_input_index, socket) in node.inputs.iter().enumerate() {
+                for conn in socket {
+                    edges.push(Edge {
+                        source_id: conn.id.clone(),
+                        source_output_index: conn.output_index,
+                        target_id: node.id.clone(),
+                        target_input_index,
+                    });
+                }
+            }
+        }
+        (nodes, edges)
+    }
+
+    /// Replaces the current graph with the document's. Old nodes are upgraded with
+    /// the registered migrations first; then every node type is checked against the
+    /// registry and every edge goes through the same validation as `add_edge`.
+    /// On any error the current graph is left untouched.
+    pub fn import_graph(&mut self, mut doc: GraphDocument) -> Result<MigrationReport, EngineError> {
+        if doc.version > GRAPH_FORMAT_VERSION {
+            return Err(EngineError::UnsupportedVersion { version: doc.version });
+        }
+        let report = doc.migrate(&self.migrations);
+
+        let previous_nodes = std::mem::take(&mut self.nodes);
+        let previous_cache = std::mem::take(&mut self.cache);
+        let previous_history = std::mem::take(&mut self.history);
+        match self.load_document(doc) {
+            Ok(()) => {
+                self.history.clear();
+                Ok(report)
+            }
+            Err(err) => {
+                self.nodes = previous_nodes;
+                self.cache = previous_cache;
+                self.history = previous_history;
+                Err(err)
+            }
+        }
+    }
+
+    fn load_document(&mut self, doc: GraphDocument) -> Result<(), EngineError> {
+        for doc_node in doc.nodes {
+            if self.nodes.contains_key(&doc_node.id) {
+                return Err(EngineError::DuplicateNodeId { node_id: doc_node.id });
+            }
+            let group = match doc_node.group {
+                Some(group_doc) => Some(Box::new(self.load_group(*group_doc)?)),
+                None => None,
+            };
+            self.insert_node(doc_node.id, doc_node.node_type, doc_node.data, doc_node.ui, group)?;
+        }
+        for edge in doc.edges {
+            self.add_edge(edge.source_id, edge.source_output_index, edge.target_id, edge.target_input_index, ConnectionPolicy::Reject)?;
+        }
+        Ok(())
+    }
+
+    // Builds a group's inner graph with the same checks as a top-level document
+    fn load_group(&mut self, doc: GroupDocument) -> Result<GroupGraph, EngineError> {
+        let outer_nodes = std::mem::take(&mut self.nodes);
+        let outer_cache = std::mem::take(&mut self.cache);
+        let outer_history = std::mem::take(&mut self.history);
+        let result = self.load_document(GraphDocument { version: GRAPH_FORMAT_VERSION, nodes: doc.nodes, edges: doc.edges });
+        let nodes = std::mem::replace(&mut self.nodes, outer_nodes);
+        self.cache = outer_cache;
+        self.history = outer_history;
+        result?;
+        let mut group = GroupGraph { nodes, inputs: doc.inputs, outputs: doc.outputs };
+        group.refresh_interface();
+        Ok(group)
+    }
+
+    pub fn remove_node(&mut self, id: &str) -> Result<Vec<String>, EngineError> {
+        if !self.nodes.contains_key(id) {
+            return Err(EngineError::NodeNotFound { node_id: id.to_string() });
+        }
+
+        // Everything downstream loses an input, so its cached values go too
+        let mut dirty_nodes = self.invalidate_downstream(id);
+        dirty_nodes.retain(|dirty| dirty != id);
+
+        // Undo re-creates the node with its data and all of its wiring
+        let restore = self.restore_node_ops(id);
+        self.history.record(vec![GraphOp::RemoveNode { id: id.to_string() }], restore);
+
+        // It is simpler to just replicate the TS logic:
+        // iterate all neighbors, filter out connections to `id`.
+        let id_string = id.to_string();
+
+        let neighbors: Vec<String> = self.nodes.iter()
+            .filter(|(_, n)| {
+                 n.inputs.iter().any(|socket: &Vec<InputConnection>| socket.iter().any(|c| c.id == id_string)) ||
+                 n.outputs.iter().any(|socket: &Vec<OutputConnection>| socket.iter().any(|c| c.id == id_string))
+            })
+            .map(|(k, _): (&String, &Node)| k.clone())
+            .collect();
+
+        for neighbor_id in neighbors {
+             if let Some(node) = self.nodes.get_mut(&neighbor_id) {
+                 // Remove from inputs
+                 for socket in &mut node.inputs {
+                     socket.retain(|c: &InputConnection| c.id != id_string);
+                 }
+                 // Remove from outputs
+                 for socket in &mut node.outputs {
+                     socket.retain(|c: &OutputConnection| c.id != id_string);
+                 }
+             }
+        }
+
+        // 3. Delete the node
+        self.nodes.remove(id);
+
+        Ok(dirty_nodes)
+    }
+
+    pub fn add_edge(&mut self, source_id: String, source_output_index: usize, target_id: String, target_input_index: usize, policy: ConnectionPolicy) -> Result<EdgeUpdate, EngineError> {
+        let edge = Edge {
+            source_id,
+            source_output_index,
+            target_id,
+            target_input_index,
+        };
+        self.connect(edge, policy, None)
+    }
+
+    // `add_edge`, optionally inserting the edge at `position` instead of last
+    fn connect(&mut self, edge: Edge, policy: ConnectionPolicy, position: Option<EdgePosition>) -> Result<EdgeUpdate, EngineError> {
+        let (source_output_index, target_input_index) = (edge.source_output_index, edge.target_input_index);
+
+        // Check availability, socket bounds, types and cycles before touching
+        // either node, so a refused edge never leaves a half-connection behind.
+        self.check_connection(&edge)?;
+
+        // Connecting the same sockets twice is a no-op.
+        if self.nodes[&edge.target_id].inputs[target_input_index].iter()
+            .any(|c| c.id == edge.source_id && c.output_index == source_output_index) {
+            return Ok(EdgeUpdate { dirty: Vec::new(), removed: Vec::new() });
+        }
+
+        // Enforce `max_connections` on both ends. Under `Replace`, the oldest
+        // connections are dropped to make room and reported back as `removed`.
+        let source_max = self.socket_max_connections(&self.nodes[&edge.source_id], false, source_output_index);
+        let target_max = self.socket_max_connections(&self.nodes[&edge.target_id], true, target_input_index);
+        let mut removed: Vec<Edge> = Vec::new();
+        {
+            let target_conns = &self.nodes[&edge.target_id].inputs[target_input_index];
+            if target_conns.len() >= target_max {
+                if policy == ConnectionPolicy::Reject || target_max == 0 {
+                    return Err(EngineError::SocketFull {
+                        node_id: edge.target_id.clone(),
+                        index: target_input_index,
+                        is_input: true,
+                        max: target_max,
+                    });
+                }
+                let excess = target_conns.len() + 1 - target_max;
+                for conn in &target_conns[..excess] {
+                    removed.push(Edge {
+                        source_id: conn.id.clone(),
+                        source_output_index: conn.output_index,
+                        target_id: edge.target_id.clone(),
+                        target_input_index,
+                    });
+                }
+            }
+
+            let source_conns = &self.nodes[&edge.source_id].outputs[source_output_index];
+            if source_conns.len() >= source_max {
+                if policy == ConnectionPolicy::Reject || source_max == 0 {
+                    return Err(EngineError::SocketFull {
+                        node_id: edge.source_id.clone(),
+                        index: source_output_index,
+                        is_input: false,
+                        max: source_max,
+                    });
+                }
+                let excess = source_conns.len() + 1 - source_max;
+                for conn in &source_conns[..excess] {
+                    let old = Edge {
+                        source_id: edge.source_id.clone(),
+                        source_output_index,
+                        target_id: conn.id.clone(),
+                        target_input_index: conn.input_index,
+                    };
+                    if !removed.contains(&old) {
+                        removed.push(old);
+                    }
+                }
+            }
+        }
+
+        // Where the replaced edges were, for undo (taken before any is detached)
+        let removed_positions: Vec<Option<EdgePosition>> = removed.iter().map(|old| graph::edge_position(&self.nodes, old)).collect();
+        let mut dirty = Vec::new();
+        for old in &removed {
+            self.detach_edge(old);
+            dirty.extend(self.invalidate_downstream(&old.target_id));
+        }
+
+        graph::attach_edge_at(&mut self.nodes, &edge, position);
+
+        dirty.extend(self.invalidate_downstream(&edge.target_id));
+        dirty.sort();
+        dirty.dedup();
+
+        let mut redo: Vec<GraphOp> = removed.iter().cloned().map(GraphOp::RemoveEdge).collect();
+        redo.push(restore_edge_op(edge.clone(), position));
+        let mut undo = vec![GraphOp::RemoveEdge(edge)];
+        undo.extend(removed.iter().cloned().zip(removed_positions).map(|(old, position)| restore_edge_op(old, position)));
+        self.history.record(redo, undo);
+
+        Ok(EdgeUpdate { dirty, removed })
+    }
+
+
+    /// Checks whether an edge could be added without actually adding it,
+    /// returning the error `add_edge` would fail with.
+    /// Socket capacity is not considered, since `ConnectionPolicy::Replace` can always make room.
+    pub fn can_connect(&self, source_id: String, source_output_index: usize, target_id: String, target_input_index: usize) -> Result<(), EngineError> {
+        self.check_connection(&Edge {
+            source_id,
+            source_output_index,
+            target_id,
+            target_input_index,
+        })
+    }
+
+    pub fn remove_edge(&mut self, source_id: String, source_output_index: usize, target_id: String, target_input_index: usize) -> Result<Vec<String>, EngineError> {
+         if source_id == target_id {
+             return Ok(Vec::new());
+         }
+         for id in [&source_id, &target_id] {
+             if !self.nodes.contains_key(id) {
+                 return Err(EngineError::NodeNotFound { node_id: id.clone() });
+             }
+         }
+
+        let edge = Edge {
+            source_id,
+            source_output_index,
+            target_id,
+            target_input_index,
+        };
+        let position = graph::edge_position(&self.nodes, &edge);
+        self.detach_edge(&edge);
+        if position.is_some() {
+            self.history.record(vec![GraphOp::RemoveEdge(edge.clone())], vec![restore_edge_op(edge.clone(), position)]);
+        }
+
+        let dirty = self.invalidate_downstream(&edge.target_id);
+        Ok(dirty)
+    }
+
+
+    pub fn update_node_data(&mut self, id: &str, parsed_data: Vec<SerdeValue>) -> Result<Vec<String>, EngineError> {
+        let node = self.nodes.get_mut(id).ok_or_else(|| EngineError::NodeNotFound { node_id: id.to_string() })?;
+        let old_data = std::mem::replace(&mut node.data, parsed_data.clone());
+        self.history.record(
+            vec![GraphOp::SetData { id: id.to_string(), data: parsed_data }],
+            vec![GraphOp::SetData { id: id.to_string(), data: old_data }],
+        );
+        Ok(self.invalidate_downstream(id))
+    }
+
+    /// Applies `ops` in order as one all-or-nothing step: if any op fails, the
+    /// graph and history are left as they were. Caches are invalidated once at
+    /// the end, and the combined dirty set is returned (each ID once).
+    pub fn apply_batch(&mut self, ops: Vec<GraphOp>) -> Result<Vec<String>, EngineError> {
+        let previous_nodes = self.nodes.clone();
+        let previous_history = self.history.clone();
+
+        self.pending_invalidation = Some(Vec::new());
+        self.history.begin_group("Batch".to_string());
+        let result = ops.iter().try_for_each(|op| self.apply_op(op).map(drop));
+        self.history.end_group();
+        let mut roots = self.pending_invalidation.take().unwrap_or_default();
+
+        if let Err(err) = result {
+            // Nothing was invalidated yet, so the cache is still valid for the old graph
+            self.nodes = previous_nodes;
+            self.history = previous_history;
+            return Err(err);
+        }
+
+        // Added nodes and new data need evaluating even when nothing upstream
+        // changed (e.g. a node removed and re-added under the same ID)
+        roots.extend(ops.into_iter().filter_map(|op| match op {
+            GraphOp::AddNode { id, .. } | GraphOp::SetData { id, .. } => Some(id),
+            _ => None,
+        }));
+        let mut dirty = self.invalidate_roots(roots);
+        dirty.sort();
+        dirty.retain(|id| self.nodes.contains_key(id));
+        Ok(dirty)
+    }
+
+    /// Reverts the last undo step. Returns `None` if there is nothing to undo.
+    /// If the step cannot be replayed, the graph and history are left as they were.
+    pub fn undo(&mut self) -> Result<Option<HistoryUpdate>, EngineError> {
+        let previous_history = self.history.clone();
+        let Some(entry) = self.history.take_undo() else {
+            return Ok(None);
+        };
+        let update = self.replay(&entry.undo, &entry, previous_history)?;
+        self.history.finish_undo(entry);
+        Ok(Some(update))
+    }
+
+    /// Re-applies the last undone step. Returns `None` if there is nothing to redo.
+    /// Fails like `undo`, without changing anything.
+    pub fn redo(&mut self) -> Result<Option<HistoryUpdate>, EngineError> {
+        let previous_history = self.history.clone();
+        let Some(entry) = self.history.take_redo() else {
+            return Ok(None);
+        };
+        let update = self.replay(&entry.redo, &entry, previous_history)?;
+        self.history.finish_redo(entry);
+        Ok(Some(update))
+    }
+
+    /// Groups every mutation until the matching `end_group` into one named undo step.
+    pub fn begin_group(&mut self, label: String) {
+        self.history.begin_group(label);
+    }
+
+    pub fn end_group(&mut self) {
+        self.history.end_group();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Moves the nodes `ids` into a new group node `group_id`. Edges crossing
+    /// the selection are routed through interface nodes: one group input per
+    /// outside output feeding the selection, and one group output per inside
+    /// output used outside. Returns the invalidated node IDs.
+    pub fn group_nodes(&mut self, group_id: String, ids: Vec<String>) -> Result<Vec<String>, EngineError> {
+        if self.nodes.contains_key(&group_id) {
+            return Err(EngineError::DuplicateNodeId { node_id: group_id });
+        }
+        let mut selected: HashSet<&str> = HashSet::new();
+        for id in &ids {
+            if !self.nodes.contains_key(id) {
+                return Err(EngineError::NodeNotFound { node_id: id.clone() });
+            }
+            if !selected.insert(id) {
+                return Err(EngineError::DuplicateNodeId { node_id: id.clone() });
+            }
+        }
+
+        let (mut incoming, mut outgoing) = (Vec::new(), Vec::new());
+        for id in &ids {
+            for edge in self.nodes[id].edges() {
+                if !selected.contains(edge.source_id.as_str()) {
+                    incoming.push(edge);
+                } else if !selected.contains(edge.target_id.as_str()) {
+                    outgoing.push(edge);
+                }
+            }
+        }
+
+        // A path outside the selection leading back into it would become a loop through the group
+        for out_edge in &outgoing {
+            for in_edge in &incoming {
+                if let Some(path) = self.find_downstream_path(&out_edge.target_id, &in_edge.source_id) {
+                    let mut cycle = vec![group_id.clone()];
+                    cycle.extend(path);
+                    cycle.push(group_id.clone());
+                    return Err(EngineError::Cycle { source_id: in_edge.source_id.clone(), target_id: group_id, cycle });
+                }
+            }
+        }
+
+        // One interface socket per distinct source socket crossing the boundary
+        let socket_of = |edge: &Edge| (edge.source_id.clone(), edge.source_output_index);
+        let mut input_sources: Vec<(String, usize)> = Vec::new();
+        for edge in &incoming {
+            if !input_sources.contains(&socket_of(edge)) {
+                input_sources.push(socket_of(edge));
+            }
+        }
+        let mut output_sources: Vec<(String, usize)> = Vec::new();
+        for edge in &outgoing {
+            if !output_sources.contains(&socket_of(edge)) {
+                output_sources.push(socket_of(edge));
+            }
+        }
+
+        let mut group = GroupGraph::default();
+        for id in &ids {
+            let node = self.nodes.remove(id).expect("checked above");
+            self.cache.remove(id);
+            group.nodes.insert(id.clone(), node);
+        }
+        for edge in incoming.iter().chain(&outgoing) {
+            graph::detach_edge(&mut self.nodes, edge);
+            graph::detach_edge(&mut group.nodes, edge);
+        }
+
+        for (k, source) in input_sources.iter().enumerate() {
+            let feeds: Vec<&Edge> = incoming.iter().filter(|edge| socket_of(edge) == *source).collect();
+            // Named after the first socket it feeds
+            let name = self.socket_name(&group.nodes[&feeds[0].target_id], true, feeds[0].target_input_index);
+            let interface_id = group::unique_id(&group.nodes, &format!("{}-in-{}", group_id, k + 1), |_| false);
+            group.nodes.insert(interface_id.clone(), Node {
+                id: interface_id.clone(),
+                node_type: group::GROUP_INPUT_NODE.to_string(),
+                inputs: Vec::new(),
+                outputs: vec![Vec::new()],
+                data: vec![SerdeValue::String(name)],
+                ui: None,
+                group: None,
+            });
+            for edge in feeds {
+                graph::attach_edge(&mut group.nodes, &Edge { source_id: interface_id.clone(), source_output_index: 0, ..edge.clone() });
+            }
+            group.inputs.push(interface_id);
+        }
+        for (k, (source_id, source_output_index)) in output_sources.iter().enumerate() {
+            let name = self.socket_name(&group.nodes[source_id], false, *source_output_index);
+            let interface_id = group::unique_id(&group.nodes, &format!("{}-out-{}", group_id, k + 1), |_| false);
+            group.nodes.insert(interface_id.clone(), Node {
+                id: interface_id.clone(),
+                node_type: group::GROUP_OUTPUT_NODE.to_string(),
+                inputs: vec![Vec::new()],
+                outputs: Vec::new(),
+                data: vec![SerdeValue::String(name)],
+                ui: None,
+                group: None,
+            });
+            graph::attach_edge(&mut group.nodes, &Edge {
+                source_id: source_id.clone(),
+                source_output_index: *source_output_index,
+                target_id: interface_id.clone(),
+                target_input_index: 0,
+            });
+            group.outputs.push(interface_id);
+        }
+
+        self.nodes.insert(group_id.clone(), Node {
+            id: group_id.clone(),
+            node_type: group::GROUP_NODE.to_string(),
+            inputs: vec![Vec::new(); input_sources.len()],
+            outputs: vec![Vec::new(); output_sources.len()],
+            data: Vec::new(),
+            ui: None,
+            group: Some(Box::new(group)),
+        });
+        for (k, (source_id, source_output_index)) in input_sources.into_iter().enumerate() {
+            graph::attach_edge(&mut self.nodes, &Edge { source_id, source_output_index, target_id: group_id.clone(), target_input_index: k });
+        }
+        for edge in outgoing {
+            let k = output_sources.iter().position(|source| *source == socket_of(&edge)).expect("collected above");
+            graph::attach_edge(&mut self.nodes, &Edge { source_id: group_id.clone(), source_output_index: k, ..edge });
+        }
+
+        self.history.record(
+            vec![GraphOp::GroupNodes { group_id: group_id.clone(), ids: ids.clone() }],
+            vec![GraphOp::Ungroup { id: group_id.clone() }],
+        );
+        Ok(self.invalidate_downstream(&group_id))
+    }
+
+    /// Replaces group `id` by its inner nodes, connecting what fed each group
+    /// input straight to the nodes behind it (and likewise for outputs).
+    /// Inner node IDs already used in this graph get a suffix. Connections that
+    /// fail the usual type / cycle checks are not made and come back in `removed`.
+    pub fn ungroup(&mut self, id: &str) -> Result<EdgeUpdate, EngineError> {
+        let node = self.nodes.get(id).ok_or_else(|| EngineError::NodeNotFound { node_id: id.to_string() })?;
+        if group::as_group(node).is_none() {
+            return Err(EngineError::NotAGroup { node_id: id.to_string() });
+        }
+        let outer_edges = node.edges();
+        let mut dirty = self.invalidate_downstream(id);
+        let node = self.nodes.remove(id).expect("checked above");
+        for edge in &outer_edges {
+            self.detach_edge(edge);
+        }
+        let GroupGraph { nodes: mut inner, inputs, outputs } = *node.group.expect("checked above");
+
+        // Interface nodes stay behind; remember what they were wired to
+        let interface_edges = |ids: &[String], inner: &HashMap<String, Node>| -> Vec<Vec<Edge>> {
+            ids.iter().map(|interface_id| inner.get(interface_id).map(Node::edges).unwrap_or_default()).collect()
+        };
+        let input_feeds = interface_edges(&inputs, &inner);
+        let output_feeds = interface_edges(&outputs, &inner);
+        for interface_id in inputs.iter().chain(&outputs) {
+            if let Some(interface) = inner.remove(interface_id) {
+                for edge in interface.edges() {
+                    graph::detach_edge(&mut inner, &edge);
+                }
+            }
+        }
+
+        let mut inner_ids: Vec<String> = inner.keys().cloned().collect();
+        inner_ids.sort();
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        for inner_id in &inner_ids {
+            if self.nodes.contains_key(inner_id) {
+                let new_id = group::unique_id(&self.nodes, inner_id, |candidate| {
+                    inner.contains_key(candidate) || renamed.values().any(|taken| taken == candidate)
+                });
+                renamed.insert(inner_id.clone(), new_id);
+            }
+        }
+        let rename = |old: &str| renamed.get(old).cloned().unwrap_or_else(|| old.to_string());
+
+        let mut moved = Vec::with_capacity(inner.len());
+        for (_, mut inner_node) in inner {
+            inner_node.id = rename(&inner_node.id);
+            for conn in inner_node.inputs.iter_mut().flatten() {
+                conn.id = rename(&conn.id);
+            }
+            for conn in inner_node.outputs.iter_mut().flatten() {
+                conn.id = rename(&conn.id);
+            }
+            moved.push(inner_node.id.clone());
+            self.nodes.insert(inner_node.id.clone(), inner_node);
+        }
+        moved.sort();
+
+        let mut removed = Vec::new();
+        for outer in &outer_edges {
+            let through: Vec<Edge> = if outer.target_id == id {
+                input_feeds.get(outer.target_input_index).into_iter().flatten()
+                    .map(|feed| Edge { target_id: rename(&feed.target_id), target_input_index: feed.target_input_index, ..outer.clone() })
+                    .collect()
+            } else {
+                output_feeds.get(outer.source_output_index).into_iter().flatten()
+                    .map(|feed| Edge { source_id: rename(&feed.source_id), source_output_index: feed.source_output_index, ..outer.clone() })
+                    .collect()
+            };
+            for edge in through {
+                match self.check_connection(&edge) {
+                    Ok(()) => graph::attach_edge(&mut self.nodes, &edge),
+                    Err(_) => removed.push(edge),
+                }
+            }
+        }
+
+        for moved_id in &moved {
+            dirty.extend(self.invalidate_downstream(moved_id));
+        }
+        dirty.retain(|dirty_id| dirty_id != id);
+        dirty.sort();
+        dirty.dedup();
+
+        self.history.record(
+            vec![GraphOp::Ungroup { id: id.to_string() }],
+            vec![GraphOp::GroupNodes { group_id: id.to_string(), ids: moved }],
+        );
+        Ok(EdgeUpdate { dirty, removed })
+    }
+
+    /// Makes the inner graph of group `id` (or the body of loop node `id`) the
+    /// current graph: every graph method (editing, evaluation, undo/redo,
+    /// export...) then acts on it until `exit_group`. Groups can be entered recursively.
+    pub fn enter_group(&mut self, id: &str) -> Result<(), EngineError> {
+        let node = self.nodes.get(id).ok_or_else(|| EngineError::NodeNotFound { node_id: id.to_string() })?;
+        if node.group.is_none() {
+            return Err(EngineError::NotAGroup { node_id: id.to_string() });
+        }
+        let input_preview = self.group_input_preview(id);
+        let group = self.nodes.get_mut(id).and_then(|node| node.group.as_mut()).expect("checked above");
+        let inner = std::mem::take(&mut group.nodes);
+        self.scopes.push(Scope {
+            group_id: id.to_string(),
+            entered: inner.clone(),
+            nodes: std::mem::replace(&mut self.nodes, inner),
+            cache: std::mem::take(&mut self.cache),
+            history: std::mem::take(&mut self.history),
+            input_preview,
+        });
+        Ok(())
+    }
+
+    // Evaluates what is connected to each input socket of group `id`, keyed by
+    // the inner input node. Sockets with nothing (or a failure) connected are left out.
+    fn group_input_preview(&mut self, id: &str) -> HashMap<String, SerdeValue> {
+        let Some(node) = self.nodes.get(id) else {
+            return HashMap::new();
+        };
+        let Some(group) = group::as_group(node) else {
+            return HashMap::new();
+        };
+        let sources: Vec<(String, InputConnection)> = group.inputs.iter().zip(&node.inputs)
+            .filter_map(|(input_id, socket)| socket.first().map(|conn| (input_id.clone(), conn.clone())))
+            .collect();
+        sources.into_iter()
+            .filter_map(|(input_id, conn)| {
+                self.evaluate_node(&conn.id).ok()?;
+                match self.cache.get(&conn.id) {
+                    Some(Ok(outputs)) => outputs.get(conn.output_index).map(|value| (input_id, value.clone())),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns to the enclosing graph (`None` if already at the top level).
+    /// Interface nodes added or removed inside change the group's sockets; outside
+    /// edges on a removed socket are dropped and come back in `removed`.
+    /// Everything edited inside becomes a single step of the enclosing history.
+    pub fn exit_group(&mut self) -> Option<EdgeUpdate> {
+        let scope = self.scopes.pop()?;
+        let edited = self.history.can_undo();
+        let inner = std::mem::replace(&mut self.nodes, scope.nodes);
+        self.cache = scope.cache;
+        self.history = scope.history;
+        let group_id = scope.group_id;
+
+        let node = self.nodes.get_mut(&group_id).expect("a group is kept while it is entered");
+        let group = node.group.as_mut().expect("a group is kept while it is entered");
+        group.nodes = scope.entered;
+        let before = if edited { self.restore_node_ops(&group_id) } else { Vec::new() };
+
+        let node = self.nodes.get_mut(&group_id).expect("a group is kept while it is entered");
+        let is_group = node.node_type == group::GROUP_NODE;
+        let group = node.group.as_mut().expect("a group is kept while it is entered");
+        group.nodes = inner;
+        let (input_map, output_map) = group.refresh_interface();
+        // A loop body's interface doesn't change the loop node's own sockets
+        let (input_map, output_map) = if is_group {
+            (input_map, output_map)
+        } else {
+            ((0..node.inputs.len()).map(Some).collect(), (0..node.outputs.len()).map(Some).collect())
+        };
+        let (input_count, output_count) = if is_group { (group.inputs.len(), group.outputs.len()) } else { (node.inputs.len(), node.outputs.len()) };
+        let unchanged = |map: &[Option<usize>], count: usize| map.len() == count && map.iter().enumerate().all(|(i, new)| *new == Some(i));
+
+        let mut removed = Vec::new();
+        if !unchanged(&input_map, input_count) || !unchanged(&output_map, output_count) {
+            // Socket indices moved: re-attach the outside edges at their new index
+            let outer_edges = node.edges();
+            node.inputs = vec![Vec::new(); input_count];
+            node.outputs = vec![Vec::new(); output_count];
+            for edge in outer_edges {
+                self.detach_edge(&edge);
+                let moved = if edge.target_id == group_id {
+                    input_map.get(edge.target_input_index).copied().flatten().map(|index| Edge { target_input_index: index, ..edge.clone() })
+                } else {
+                    output_map.get(edge.source_output_index).copied().flatten().map(|index| Edge { source_output_index: index, ..edge.clone() })
+                };
+                match moved {
+                    Some(moved) => graph::attach_edge(&mut self.nodes, &moved),
+                    None => removed.push(edge),
+                }
+            }
+        }
+
+        if edited {
+            // Re-creating the group swaps in the whole inner graph and its
+            // wiring, so older steps out here still see the sockets they knew
+            let remove = GraphOp::RemoveNode { id: group_id.clone() };
+            let mut redo = vec![remove.clone()];
+            redo.extend(self.restore_node_ops(&group_id));
+            let mut undo = vec![remove];
+            undo.extend(before);
+            self.history.record(redo, undo);
+        }
+
+        let dirty = self.invalidate_downstream(&group_id);
+        Some(EdgeUpdate { dirty, removed })
+    }
+
+    /// IDs of the groups entered with `enter_group`, outermost first.
+    pub fn scope(&self) -> Vec<String> {
+        self.scopes.iter().map(|scope| scope.group_id.clone()).collect()
+    }
+
+    /// Schema of one node; for a group it is derived from its interface.
+    pub fn node_schema(&self, id: &str) -> Option<NodeSchema> {
+        let node = self.nodes.get(id)?;
+        match group::as_group(node) {
+            Some(group) => Some(group.schema()),
+            None => self.node_registry.get(&node.node_type).map(|def| def.schema.clone()),
+        }
+    }
+
+    /// Checks the current graph (and the group / loop bodies in it) for
+    /// one-sided edges, socket and data arrays that do not match the schema,
+    /// and unknown node types.
+    pub fn validate(&self) -> ValidationReport {
+        validate::validate(&self.nodes, &self.node_registry)
+    }
+
+    /// Fixes what `validate` reports as repairable. Not recorded in history:
+    /// undoing it would only bring the inconsistency back.
+    pub fn repair(&mut self) -> RepairReport {
+        let found = self.validate().issues;
+        if !found.iter().any(|issue| issue.repairable) {
+            return RepairReport { repaired: Vec::new(), remaining: found, dirty: Vec::new() };
+        }
+        validate::repair(&mut self.nodes, &self.node_registry);
+
+        let remaining = self.validate().issues;
+        let repaired: Vec<_> = found.into_iter().filter(|issue| !remaining.contains(issue)).collect();
+        // Top-level nodes touched, directly or through their body
+        let mut roots: Vec<String> = repaired.iter()
+            .map(|issue| issue.path.first().unwrap_or(&issue.node_id).clone())
+            .filter(|id| self.nodes.contains_key(id))
+            .collect();
+        roots.sort();
+        roots.dedup();
+        let dirty = self.invalidate_roots(roots);
+        RepairReport { repaired, remaining, dirty }
+    }
+
+    fn socket_name(&self, node: &Node, is_input: bool, index: usize) -> String {
+        match group::as_group(node) {
+            Some(group) => {
+                let schema = group.schema();
+                let sockets = if is_input { schema.io.inputs } else { schema.io.outputs };
+                sockets.into_iter().nth(index).map(|s| s.name).unwrap_or_default()
+            }
+            None => definitions::find_socket(&self.node_registry, &node.node_type, is_input, index)
+                .map(|s| s.name.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    // Applies one step's ops. If one fails, the graph and history (as they
+    // were before the step was taken) are put back, as in `apply_batch`.
+    fn replay(&mut self, ops: &[GraphOp], entry: &HistoryEntry, previous_history: History) -> Result<HistoryUpdate, EngineError> {
+        let previous_nodes = self.nodes.clone();
+        let mut dirty = Vec::new();
+        for op in ops {
+            match self.apply_op(op) {
+                Ok(op_dirty) => dirty.extend(op_dirty),
+                Err(err) => {
+                    // Only invalidation happened, so the cache still fits the old graph
+                    self.nodes = previous_nodes;
+                    self.history = previous_history;
+                    return Err(err);
+                }
+            }
+        }
+        dirty.sort();
+        dirty.dedup();
+        // A node that no longer exists has nothing to re-render
+        dirty.retain(|id| self.nodes.contains_key(id));
+        Ok(HistoryUpdate {
+            label: entry.label.clone(),
+            dirty,
+            changes: ops.to_vec(),
+        })
+    }
+
+    fn apply_op(&mut self, op: &GraphOp) -> Result<Vec<String>, EngineError> {
+        match op.clone() {
+            GraphOp::AddNode { id, node_type, data, ui, group } => {
+                self.insert_node(id.clone(), node_type, data, ui, group)?;
+                Ok(vec![id])
+            }
+            GraphOp::RemoveNode { id } => self.remove_node(&id),
+            GraphOp::AddEdge(edge) => Ok(self.add_edge(edge.source_id, edge.source_output_index, edge.target_id, edge.target_input_index, ConnectionPolicy::Reject)?.dirty),
+            GraphOp::RestoreEdge { edge, position } => Ok(self.connect(edge, ConnectionPolicy::Reject, Some(position))?.dirty),
+            GraphOp::RemoveEdge(edge) => self.remove_edge(edge.source_id, edge.source_output_index, edge.target_id, edge.target_input_index),
+            GraphOp::SetData { id, data } => self.update_node_data(&id, data),
+            GraphOp::GroupNodes { group_id, ids } => self.group_nodes(group_id, ids),
+            GraphOp::Ungroup { id } => Ok(self.ungroup(&id)?.dirty),
+        }
+    }
+
+    /// Removes both halves of an edge (source output and target input), if present.
+    fn detach_edge(&mut self, edge: &Edge) {
+        graph::detach_edge(&mut self.nodes, edge);
+    }
+
+    /// Everything `add_edge` requires of a new edge apart from socket capacity:
+    /// both nodes exist, both socket indices are in range, the socket types are
+    /// compatible and the edge would not close a cycle.
+    fn check_connection(&self, edge: &Edge) -> Result<(), EngineError> {
+        let source_node = self.nodes.get(&edge.source_id).ok_or_else(|| EngineError::NodeNotFound { node_id: edge.source_id.clone() })?;
+        let target_node = self.nodes.get(&edge.target_id).ok_or_else(|| EngineError::NodeNotFound { node_id: edge.target_id.clone() })?;
+        if edge.source_output_index >= source_node.outputs.len() {
+            return Err(EngineError::InvalidSocketIndex {
+                node_id: edge.source_id.clone(),
+                index: edge.source_output_index,
+                is_input: false,
+                count: source_node.outputs.len(),
+            });
+        }
+        if edge.target_input_index >= target_node.inputs.len() {
+            return Err(EngineError::InvalidSocketIndex {
+                node_id: edge.target_id.clone(),
+                index: edge.target_input_index,
+                is_input: true,
+                count: target_node.inputs.len(),
+            });
+        }
+
+        // Validate the source output type against the target input type
+        // using the declared coercion table.
+        let from = self.socket_type(&source_node.node_type, false, edge.source_output_index);
+        let to = self.socket_type(&target_node.node_type, true, edge.target_input_index);
+        if definitions::check_type_compatibility(from, to).is_err() {
+            return Err(EngineError::TypeMismatch {
+                source_id: edge.source_id.clone(),
+                source_output_index: edge.source_output_index,
+                target_id: edge.target_id.clone(),
+                target_input_index: edge.target_input_index,
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+
+        // Cycle detection: the new edge closes a loop if `source` is already
+        // reachable downstream of `target` (this also covers self-loops).
+        if let Some(cycle) = self.find_downstream_path(&edge.target_id, &edge.source_id) {
+            return Err(EngineError::Cycle {
+                source_id: edge.source_id.clone(),
+                target_id: edge.target_id.clone(),
+                cycle,
+            });
+        }
+        Ok(())
+    }
+
+    fn socket_type(&self, node_type: &str, is_input: bool, index: usize) -> &str {
+        definitions::find_socket(&self.node_registry, node_type, is_input, index)
+            .map(|s| s.val_type.as_str())
+            .unwrap_or("any")
+    }
+
+    fn socket_max_connections(&self, node: &Node, is_input: bool, index: usize) -> usize {
+        if let Some(group) = group::as_group(node) {
+            let schema = group.schema();
+            let sockets = if is_input { &schema.io.inputs } else { &schema.io.outputs };
+            return sockets.get(index).map_or(usize::MAX, |s| s.max_connections);
+        }
+        definitions::find_socket(&self.node_registry, &node.node_type, is_input, index)
+            .map(|s| s.max_connections)
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Drops the cached values of `id` and everything downstream of it.
+    /// Returns the invalidated node IDs (each once, `id` first).
+    /// Inside `apply_batch` this only records `id` (and its current direct
+    /// dependents, in case `id` is removed later in the batch) and returns nothing.
+    fn invalidate_downstream(&mut self, id: &str) -> Vec<String> {
+        if let Some(pending) = &mut self.pending_invalidation {
+            pending.push(id.to_string());
+            if let Some(node) = self.nodes.get(id) {
+                pending.extend(node.outputs.iter().flatten().map(|conn| conn.id.clone()));
+            }
+            return Vec::new();
+        }
+        self.invalidate_roots(vec![id.to_string()])
+    }
+
+    fn invalidate_roots(&mut self, roots: Vec<String>) -> Vec<String> {
+        // Even if a node is not in cache, its dependents might be, so always traverse.
+        // The visited set keeps diamonds from producing duplicates and guarantees
+        // termination even on a malformed (cyclic) graph.
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut dirty_ids = Vec::new();
+        let mut stack: Vec<&str> = roots.iter().rev().map(String::as_str).collect();
+
+        while let Some(current) = stack.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            dirty_ids.push(current.to_string());
+            if let Some(node) = self.nodes.get(current) {
+                for output_socket in &node.outputs {
+                    for conn in output_socket {
+                        stack.push(conn.id.as_str());
+                    }
+                }
+            }
+        }
+
+        for dirty in &dirty_ids {
+            self.cache.remove(dirty);
+        }
+        dirty_ids
+    }
+
+    /// Breadth-first search along output connections from `from` to `to`.
+    /// Returns the node IDs on the path (both ends included) if `to` is reachable.
+    fn find_downstream_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut parents: HashMap<String, Option<String>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        parents.insert(from.to_string(), None);
+        queue.push_back(from.to_string());
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![current.clone()];
+                let mut cursor = parents.get(&current).cloned().flatten();
+                while let Some(id) = cursor {
+                    cursor = parents.get(&id).cloned().flatten();
+                    path.push(id);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            if let Some(node) = self.nodes.get(&current) {
+                for socket in &node.outputs {
+                    for conn in socket {
+                        if !parents.contains_key(&conn.id) {
+                            parents.insert(conn.id.clone(), Some(current.clone()));
+                            queue.push_back(conn.id.clone());
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Ops that re-create node `id` as it is now, with all of its wiring in place
+    fn restore_node_ops(&self, id: &str) -> Vec<GraphOp> {
+        let node = &self.nodes[id];
+        let mut ops = vec![GraphOp::AddNode {
+            id: node.id.clone(),
+            node_type: node.node_type.clone(),
+            data: node.data.clone(),
+            ui: node.ui.clone(),
+            group: node.group.clone(),
+        }];
+        ops.extend(node.edges().into_iter().map(|edge| {
+            let position = graph::edge_position(&self.nodes, &edge);
+            restore_edge_op(edge, position)
+        }));
+        ops
+    }
+
+    /// Evaluates `id` and its upstream subgraph, returning the trace of every
+    /// node involved (keyed by node ID).
+    pub fn evaluate_node(&mut self, id: &str) -> Result<HashMap<String, NodeCache>, EngineError> {
+        // Inside an entered group, its input nodes give the values fed in outside
+        if let Some(scope) = self.scopes.last() {
+            for (input_id, value) in &scope.input_preview {
+                if self.nodes.contains_key(input_id) {
+                    self.cache.entry(input_id.clone()).or_insert_with(|| Ok(vec![value.clone()]));
+                }
+            }
+        }
+        let mut evaluator = Evaluator {
+            nodes: &self.nodes,
+            registry: &self.node_registry,
+            graph_types: &self.graph_types,
+            iteration_limit: self.iteration_limit,
+            numeric: self.numeric,
+            cache: &mut self.cache,
+            trace: HashMap::new(),
+            lazy_failure: None,
+        };
+        evaluator.run(id)?;
+        Ok(evaluator.trace)
+    }
+}
+
+// Op that puts `edge` back where it was, or appends it if the position is unknown
+fn restore_edge_op(edge: Edge, position: Option<EdgePosition>) -> GraphOp {
+    match position {
+        Some(position) => GraphOp::RestoreEdge { edge, position },
+        None => GraphOp::AddEdge(edge),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{add, add_with, connect, error, outputs, value};
+    use serde_json::json;
+
+    fn edge(engine: &mut GraphEngine, source: &str, output: usize, target: &str, input: usize, policy: ConnectionPolicy) -> Result<EdgeUpdate, EngineError> {
+        engine.add_edge(source.to_string(), output, target.to_string(), input, policy)
+    }
+
+    #[test]
+    fn rejects_edges_closing_a_cycle_with_the_loop_path() {
+        let mut engine = GraphEngine::new();
+        for id in ["a", "b", "c"] {
+            add(&mut engine, id, "additionNode");
+        }
+        connect(&mut engine, "a", 0, "b", 0);
+        connect(&mut engine, "b", 0, "c", 0);
+
+        let err = edge(&mut engine, "c", 0, "a", 0, ConnectionPolicy::Reject).unwrap_err();
+        assert_eq!(err.code(), "CYCLE");
+        match err {
+            EngineError::Cycle { cycle, .. } => assert_eq!(cycle, ["a", "b", "c"]),
+            other => panic!("unexpected error {:?}", other),
+        }
+        let err = edge(&mut engine, "a", 0, "a", 1, ConnectionPolicy::Reject).unwrap_err();
+        assert_eq!(err.code(), "CYCLE");
+        // Nothing was attached
+        assert!(engine.node("a").unwrap().inputs.iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn max_connections_reject_or_replace_the_oldest() {
+        let mut engine = GraphEngine::new();
+        add(&mut engine, "x", "numberNode");
+        add(&mut engine, "y", "numberNode");
+        add(&mut engine, "sum", "additionNode");
+        connect(&mut engine, "x", 0, "sum", 0);
+
+        let err = edge(&mut engine, "y", 0, "sum", 0, ConnectionPolicy::Reject).unwrap_err();
+        assert_eq!(err.code(), "SOCKET_FULL");
+
+        let update = edge(&mut engine, "y", 0, "sum", 0, ConnectionPolicy::Replace).unwrap();
+        assert_eq!(update.removed, [Edge { source_id: "x".into(), source_output_index: 0, target_id: "sum".into(), target_input_index: 0 }]);
+        assert_eq!(engine.node("sum").unwrap().inputs[0][0].id, "y");
+        assert!(engine.node("x").unwrap().outputs[0].is_empty());
+
+        // Sockets that take no connections refuse even under Replace
+        let err = edge(&mut engine, "x", 0, "y", 0, ConnectionPolicy::Replace).unwrap_err();
+        assert_eq!(err.code(), "SOCKET_FULL");
+    }
+
+    #[test]
+    fn socket_types_are_checked_on_connect() {
+        let mut engine = GraphEngine::new();
+        add(&mut engine, "text", "stringNode");
+        add(&mut engine, "n", "numberNode");
+        add(&mut engine, "root", "sqrtNode");
+        add(&mut engine, "upper", "upperCaseNode");
+
+        let err = engine.can_connect("text".into(), 0, "root".into(), 0).unwrap_err();
+        assert_eq!(err.code(), "TYPE_MISMATCH");
+        assert_eq!(edge(&mut engine, "text", 0, "root", 0, ConnectionPolicy::Reject).unwrap_err().code(), "TYPE_MISMATCH");
+
+        // number -> string is a declared coercion
+        engine.update_node_data("n", vec![json!(42)]).unwrap();
+        connect(&mut engine, "n", 0, "upper", 0);
+        assert_eq!(value(&mut engine, "upper"), json!("42"));
+
+        let err = engine.can_connect("n".into(), 3, "upper".into(), 0).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SOCKET_INDEX");
+        let err = engine.can_connect("n".into(), 0, "missing".into(), 0).unwrap_err();
+        assert_eq!(err.code(), "NODE_NOT_FOUND");
+    }
+
+    #[test]
+    fn multi_output_nodes_feed_each_socket_separately() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "a", "numberNode", vec![json!(17)]);
+        add_with(&mut engine, "b", "numberNode", vec![json!(5)]);
+        add(&mut engine, "dm", "divModNode");
+        add(&mut engine, "q", "outputNode");
+        add(&mut engine, "r", "outputNode");
+        connect(&mut engine, "a", 0, "dm", 0);
+        connect(&mut engine, "b", 0, "dm", 1);
+        connect(&mut engine, "dm", 0, "q", 0);
+        connect(&mut engine, "dm", 1, "r", 0);
+
+        assert_eq!(outputs(&mut engine, "dm"), [json!(3), json!(2)]);
+        assert_eq!(value(&mut engine, "q"), json!(3));
+        assert_eq!(value(&mut engine, "r"), json!(2));
+    }
+
+    #[test]
+    fn long_chains_evaluate_and_invalidate_without_recursion() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "n0", "numberNode", vec![json!(1)]);
+        let length = 20_000;
+        for i in 1..=length {
+            let id = format!("n{}", i);
+            add_with(&mut engine, &id, "additionNode", vec![json!(0), json!(1)]);
+            connect(&mut engine, &format!("n{}", i - 1), 0, &id, 0);
+        }
+        let last = format!("n{}", length);
+        assert_eq!(value(&mut engine, &last), json!(length + 1));
+
+        let dirty = engine.update_node_data("n0", vec![json!(2)]).unwrap();
+        assert_eq!(dirty.len(), length + 1);
+        assert_eq!(value(&mut engine, &last), json!(length + 2));
+    }
+
+    #[test]
+    fn lazy_inputs_only_evaluate_the_taken_branch() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "cond", "booleanNode", vec![json!(true)]);
+        add_with(&mut engine, "yes", "stringNode", vec![json!("yes")]);
+        add_with(&mut engine, "zero", "numberNode", vec![json!(0)]);
+        add_with(&mut engine, "one", "numberNode", vec![json!(1)]);
+        add(&mut engine, "broken", "divisionNode");
+        add(&mut engine, "if", "ifNode");
+        connect(&mut engine, "one", 0, "broken", 0);
+        connect(&mut engine, "zero", 0, "broken", 1);
+        connect(&mut engine, "cond", 0, "if", 0);
+        connect(&mut engine, "yes", 0, "if", 1);
+        connect(&mut engine, "broken", 0, "if", 2);
+
+        let trace = engine.evaluate_node("if").unwrap();
+        assert_eq!(trace["if"].outputs, [json!("yes")]);
+        assert!(!trace.contains_key("broken"));
+
+        engine.set_numeric_policy(NumericPolicy::Error);
+        engine.update_node_data("cond", vec![json!(false)]).unwrap();
+        let failure = error(&mut engine, "if");
+        assert_eq!(failure.origin, "broken");
+        assert!(failure.upstream_failed);
+    }
+
+    #[test]
+    fn failures_stay_on_their_node_and_mark_dependents() {
+        let mut engine = GraphEngine::new();
+        engine.set_numeric_policy(NumericPolicy::Error);
+        add_with(&mut engine, "one", "numberNode", vec![json!(1)]);
+        add_with(&mut engine, "zero", "numberNode", vec![json!(0)]);
+        add(&mut engine, "div", "divisionNode");
+        add(&mut engine, "sum", "additionNode");
+        add(&mut engine, "ok", "additionNode");
+        add(&mut engine, "out", "outputNode");
+        connect(&mut engine, "one", 0, "div", 0);
+        connect(&mut engine, "zero", 0, "div", 1);
+        connect(&mut engine, "div", 0, "sum", 0);
+        connect(&mut engine, "sum", 0, "out", 0);
+        connect(&mut engine, "one", 0, "ok", 0);
+
+        let trace = engine.evaluate_node("out").unwrap();
+        let div = trace["div"].error.as_ref().unwrap();
+        assert_eq!((div.code, div.upstream_failed), ("LOGIC_FAILED", false));
+        let out = trace["out"].error.as_ref().unwrap();
+        assert_eq!((out.origin.as_str(), out.upstream_failed), ("div", true));
+        // Unrelated nodes still evaluate
+        assert_eq!(value(&mut engine, "ok"), json!(1));
+    }
+
+    #[test]
+    fn export_import_round_trip_keeps_nodes_edges_and_ui() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "a", "numberNode", vec![json!(2)]);
+        add_with(&mut engine, "b", "numberNode", vec![json!(3)]);
+        add(&mut engine, "mul", "multiplicationNode");
+        connect(&mut engine, "a", 0, "mul", 0);
+        connect(&mut engine, "b", 0, "mul", 1);
+        engine.set_node_ui("mul", Some(json!({ "x": 10, "y": 20 }))).unwrap();
+
+        let json = engine.export_graph().to_json();
+        let mut copy = GraphEngine::new();
+        copy.import_graph(GraphDocument::from_json(&json).unwrap()).unwrap();
+        assert_eq!(copy.export_graph().to_json(), json);
+        assert_eq!(copy.node("mul").unwrap().ui, Some(json!({ "x": 10, "y": 20 })));
+        assert_eq!(value(&mut copy, "mul"), json!(6));
+        assert!(!copy.can_undo());
+    }
+
+    #[test]
+    fn failed_import_leaves_the_graph_untouched() {
+        let mut engine = GraphEngine::new();
+        add(&mut engine, "keep", "numberNode");
+
+        let unknown = r#"{"version":2,"nodes":[{"id":"x","type":"noSuchNode","data":[]}],"edges":[]}"#;
+        let err = engine.import_graph(GraphDocument::from_json(unknown).unwrap()).unwrap_err();
+        assert_eq!(err.code(), "UNKNOWN_NODE_TYPE");
+
+        let cyclic = r#"{"version":2,"nodes":[
+            {"id":"a","type":"additionNode","data":[0,0]},{"id":"b","type":"additionNode","data":[0,0]}],
+            "edges":[{"sourceId":"a","sourceOutputIndex":0,"targetId":"b","targetInputIndex":0},
+                     {"sourceId":"b","sourceOutputIndex":0,"targetId":"a","targetInputIndex":0}]}"#;
+        assert_eq!(engine.import_graph(GraphDocument::from_json(cyclic).unwrap()).unwrap_err().code(), "CYCLE");
+
+        let future = r#"{"version":99,"nodes":[],"edges":[]}"#;
+        assert_eq!(engine.import_graph(GraphDocument::from_json(future).unwrap()).unwrap_err().code(), "UNSUPPORTED_VERSION");
+        assert_eq!(GraphDocument::from_json("{").unwrap_err().code(), "DESERIALIZATION_FAILED");
+
+        assert_eq!(engine.nodes().map(|n| n.id.as_str()).collect::<Vec<_>>(), ["keep"]);
+    }
+
+    #[test]
+    fn deprecated_type_keys_add_the_new_type() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "sub", "subractionNode", vec![json!(5), json!(2)]);
+        assert_eq!(engine.node("sub").unwrap().node_type, "subtractionNode");
+        assert_eq!(value(&mut engine, "sub"), json!(3));
+        assert!(!engine.get_node_defs().contains_key("subractionNode"));
+
+        let doc = GraphDocument { version: GRAPH_FORMAT_VERSION, nodes: Vec::new(), edges: Vec::new() };
+        let err = engine.register_graph_node_type("subractionNode".into(), doc).unwrap_err();
+        assert_eq!(err.code(), "TYPE_ALREADY_REGISTERED");
+    }
+
+    #[test]
+    fn saved_graphs_register_as_node_types() {
+        // double(x) = x + x
+        let doc = r#"{"version":2,"nodes":[
+            {"id":"in","type":"groupInputNode","data":["x"]},
+            {"id":"sum","type":"additionNode","data":[0,0]},
+            {"id":"out","type":"groupOutputNode","data":["doubled"]}],
+            "edges":[{"sourceId":"in","sourceOutputIndex":0,"targetId":"sum","targetInputIndex":0},
+                     {"sourceId":"in","sourceOutputIndex":0,"targetId":"sum","targetInputIndex":1},
+                     {"sourceId":"sum","sourceOutputIndex":0,"targetId":"out","targetInputIndex":0}]}"#;
+        let mut engine = GraphEngine::new();
+        let schema = engine.register_graph_node_type("double".into(), GraphDocument::from_json(doc).unwrap()).unwrap();
+        assert_eq!(schema.io.inputs[0].name, "x");
+        assert_eq!(schema.io.outputs[0].name, "doubled");
+        let err = engine.register_graph_node_type("double".into(), GraphDocument::from_json(doc).unwrap()).unwrap_err();
+        assert_eq!(err.code(), "TYPE_ALREADY_REGISTERED");
+
+        add_with(&mut engine, "n", "numberNode", vec![json!(21)]);
+        add(&mut engine, "d", "double");
+        connect(&mut engine, "n", 0, "d", 0);
+        assert_eq!(value(&mut engine, "d"), json!(42));
+    }
+
+    #[test]
+    fn failed_undo_leaves_graph_and_history_untouched() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "a", "numberNode", vec![json!(1)]);
+        engine.update_node_data("a", vec![json!(2)]).unwrap();
+        // A step that no longer fits the graph: its second op fails
+        engine.history.record(
+            vec![GraphOp::SetData { id: "a".into(), data: vec![json!(3)] }],
+            vec![GraphOp::SetData { id: "a".into(), data: vec![json!(9)] }, GraphOp::RemoveNode { id: "missing".into() }],
+        );
+
+        assert_eq!(engine.undo().unwrap_err().code(), "NODE_NOT_FOUND");
+        assert_eq!(engine.node("a").unwrap().data, [json!(2)]);
+        assert!(engine.can_undo());
+        assert!(!engine.can_redo());
+        // Earlier steps are still there
+        engine.history.take_undo();
+        engine.undo().unwrap().unwrap();
+        assert_eq!(engine.node("a").unwrap().data, [json!(1)]);
+    }
+}
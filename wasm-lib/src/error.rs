@@ -0,0 +1,153 @@
+use serde::Serialize;
+use std::fmt;
+
+// --- Engine Errors ---
+
+// Every failure surfaced by `GraphEngine`. Each variant maps to a stable
+// `code` string so the frontend can branch on it instead of matching messages.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineError {
+    UnknownNodeType { node_type: String, node_id: Option<String> },
+    NodeNotFound { node_id: String },
+    InvalidSocketIndex { node_id: String, index: usize, is_input: bool, count: usize },
+    SocketFull { node_id: String, index: usize, is_input: bool, max: usize },
+    Cycle { source_id: String, target_id: String, cycle: Vec<String> },
+    TypeMismatch { source_id: String, source_output_index: usize, target_id: String, target_input_index: usize, from: String, to: String },
+    LogicFailed { node_id: String, message: String },
+    Deserialization { message: String },
+    UnsupportedVersion { version: u32 },
+    DuplicateNodeId { node_id: String },
+    NotAGroup { node_id: String },
+    TypeAlreadyRegistered { node_type: String },
+    IterationLimit { node_id: String, limit: usize },
+    InvalidInputValue { node_id: String, index: usize, expected: String, value: String },
+}
+
+impl EngineError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EngineError::UnknownNodeType { .. } => "UNKNOWN_NODE_TYPE",
+            EngineError::NodeNotFound { .. } => "NODE_NOT_FOUND",
+            EngineError::InvalidSocketIndex { .. } => "INVALID_SOCKET_INDEX",
+            EngineError::SocketFull { .. } => "SOCKET_FULL",
+            EngineError::Cycle { .. } => "CYCLE",
+            EngineError::TypeMismatch { .. } => "TYPE_MISMATCH",
+            EngineError::LogicFailed { .. } => "LOGIC_FAILED",
+            EngineError::Deserialization { .. } => "DESERIALIZATION_FAILED",
+            EngineError::UnsupportedVersion { .. } => "UNSUPPORTED_VERSION",
+            EngineError::DuplicateNodeId { .. } => "DUPLICATE_NODE_ID",
+            EngineError::NotAGroup { .. } => "NOT_A_GROUP",
+            EngineError::TypeAlreadyRegistered { .. } => "TYPE_ALREADY_REGISTERED",
+            EngineError::IterationLimit { .. } => "ITERATION_LIMIT",
+            EngineError::InvalidInputValue { .. } => "INVALID_INPUT_VALUE",
+        }
+    }
+
+    // Node IDs the error refers to (for a cycle, the nodes along the loop)
+    pub fn node_ids(&self) -> Vec<String> {
+        match self {
+            EngineError::UnknownNodeType { node_id, .. } => node_id.iter().cloned().collect(),
+            EngineError::NodeNotFound { node_id }
+            | EngineError::InvalidSocketIndex { node_id, .. }
+            | EngineError::SocketFull { node_id, .. }
+            | EngineError::LogicFailed { node_id, .. }
+            | EngineError::DuplicateNodeId { node_id }
+            | EngineError::NotAGroup { node_id }
+            | EngineError::IterationLimit { node_id, .. }
+            | EngineError::InvalidInputValue { node_id, .. } => vec![node_id.clone()],
+            EngineError::Cycle { cycle, .. } => cycle.clone(),
+            EngineError::TypeMismatch { source_id, target_id, .. } => vec![source_id.clone(), target_id.clone()],
+            EngineError::Deserialization { .. }
+            | EngineError::UnsupportedVersion { .. }
+            | EngineError::TypeAlreadyRegistered { .. } => Vec::new(),
+        }
+    }
+
+    // Sockets the error refers to
+    pub fn sockets(&self) -> Vec<SocketRef> {
+        match self {
+            EngineError::InvalidSocketIndex { node_id, index, is_input, .. }
+            | EngineError::SocketFull { node_id, index, is_input, .. } => vec![SocketRef::new(node_id, *index, *is_input)],
+            EngineError::TypeMismatch { source_id, source_output_index, target_id, target_input_index, .. } => vec![
+                SocketRef::new(source_id, *source_output_index, false),
+                SocketRef::new(target_id, *target_input_index, true),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = |is_input: &bool| if *is_input { "Input" } else { "Output" };
+        match self {
+            EngineError::UnknownNodeType { node_type, .. } => write!(f, "Unknown node type: {}", node_type),
+            EngineError::NodeNotFound { node_id } => write!(f, "Node with ID {} not found.", node_id),
+            EngineError::InvalidSocketIndex { node_id, index, is_input, count } => write!(
+                f, "Invalid {} index {} on node `{}` (it has {}).",
+                direction(is_input).to_lowercase(), index, node_id, count
+            ),
+            EngineError::SocketFull { node_id, index, is_input, max } => write!(
+                f, "{} {} of node `{}` accepts at most {} connection(s).",
+                direction(is_input), index, node_id, max
+            ),
+            EngineError::Cycle { source_id, target_id, .. } => write!(f, "Connecting `{}` to `{}` would create a cycle.", source_id, target_id),
+            EngineError::TypeMismatch { from, to, .. } => write!(f, "Cannot connect a `{}` output to a `{}` input.", from, to),
+            EngineError::LogicFailed { node_id, message } => write!(f, "Node `{}` failed: {}", node_id, message),
+            EngineError::Deserialization { message } => write!(f, "Failed to deserialize: {}", message),
+            EngineError::UnsupportedVersion { version } => write!(f, "Unsupported graph document version {} (expected at most {}).", version, crate::document::GRAPH_FORMAT_VERSION),
+            EngineError::DuplicateNodeId { node_id } => write!(f, "Node ID {} is used more than once.", node_id),
+            EngineError::NotAGroup { node_id } => write!(f, "Node `{}` is not a group.", node_id),
+            EngineError::TypeAlreadyRegistered { node_type } => write!(f, "Node type {} is already registered.", node_type),
+            EngineError::IterationLimit { node_id, limit } => write!(f, "Node `{}` would run more than {} iterations.", node_id, limit),
+            EngineError::InvalidInputValue { node_id, index, expected, value } => write!(f, "Input {} of node `{}` expects a {}, got {}.", index, node_id, expected, value),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+// --- Socket References ---
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SocketRef {
+    #[serde(rename = "nodeId")]
+    pub node_id: String,
+    pub index: usize,
+    pub direction: &'static str, // "input", "output"
+}
+
+impl SocketRef {
+    pub fn new(node_id: &str, index: usize, is_input: bool) -> Self {
+        SocketRef {
+            node_id: node_id.to_string(),
+            index,
+            direction: if is_input { "input" } else { "output" },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_carry_codes_nodes_and_sockets() {
+        let err = EngineError::TypeMismatch {
+            source_id: "a".into(),
+            source_output_index: 0,
+            target_id: "b".into(),
+            target_input_index: 1,
+            from: "string".into(),
+            to: "number".into(),
+        };
+        assert_eq!(err.code(), "TYPE_MISMATCH");
+        assert_eq!(err.node_ids(), ["a", "b"]);
+        assert_eq!(err.sockets(), [SocketRef::new("a", 0, false), SocketRef::new("b", 1, true)]);
+        assert_eq!(err.to_string(), "Cannot connect a `string` output to a `number` input.");
+
+        let err = EngineError::Cycle { source_id: "c".into(), target_id: "a".into(), cycle: vec!["a".into(), "b".into(), "c".into()] };
+        assert_eq!(err.node_ids(), ["a", "b", "c"]);
+        assert!(err.sockets().is_empty());
+    }
+}
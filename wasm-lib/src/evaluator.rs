@@ -0,0 +1,425 @@
+use crate::definitions;
+use crate::error::EngineError;
+use crate::functions;
+use crate::graph::Node;
+use crate::group::{self, GroupGraph};
+use crate::iteration;
+use crate::numeric::{self, NumericSettings};
+use serde::Serialize;
+use serde_json::Value as SerdeValue;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeCache {
+    pub inputs: Vec<Option<SerdeValue>>,
+    pub outputs: Vec<SerdeValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<NodeError>,
+}
+
+/// Why a node has no value. `origin` is the node whose logic actually failed;
+/// nodes that only failed because one of their inputs did are marked `upstreamFailed`.
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeError {
+    pub code: &'static str, // Same codes as `EngineError::code`
+    pub message: String,
+    pub origin: String,
+    #[serde(rename = "upstreamFailed")]
+    pub upstream_failed: bool,
+}
+
+/// Cached evaluation state of a node: one value per output socket, or the error.
+pub(crate) type NodeResult = Result<Vec<SerdeValue>, NodeError>;
+
+/// One evaluation pass over the graph. Holds the borrowed graph and cache so
+/// that lazy inputs requested from inside node logic can schedule and
+/// evaluate their own upstream subgraph, adding to the same trace.
+pub(crate) struct Evaluator<'a> {
+    pub(crate) nodes: &'a HashMap<String, Node>,
+    pub(crate) registry: &'a HashMap<String, definitions::NodeDefinition>,
+    // Inner graphs of node types registered with `register_graph_node_type`
+    pub(crate) graph_types: &'a HashMap<String, GroupGraph>,
+    // Most iterations a single loop node may run
+    pub(crate) iteration_limit: usize,
+    pub(crate) numeric: NumericSettings,
+    pub(crate) cache: &'a mut HashMap<String, NodeResult>,
+    pub(crate) trace: HashMap<String, NodeCache>,
+    // Set when a lazy input requested by the running logic failed upstream
+    pub(crate) lazy_failure: Option<NodeError>,
+}
+
+impl<'a> Evaluator<'a> {
+    /// Schedules the (eager) upstream subgraph of `id` once, then evaluates it in
+    /// dependency order without recursion. Nodes that are still cached are reused.
+    pub(crate) fn run(&mut self, id: &str) -> Result<(), EngineError> {
+        let order = self.upstream_order(id)?;
+        for node_id in &order {
+            // Already handled, e.g. by an earlier lazy request in this pass
+            if self.trace.contains_key(node_id) {
+                continue;
+            }
+            self.eval_node(node_id)?;
+        }
+        Ok(())
+    }
+
+    fn eval_node(&mut self, node_id: &str) -> Result<(), EngineError> {
+        let nodes = self.nodes;
+        let node = &nodes[node_id];
+
+        let result = match self.cache.get(node_id) {
+            Some(cached) => cached.clone(),
+            None => {
+                // A failed eager input fails this node without running its logic
+                let upstream_failure = (0..node.inputs.len())
+                    .filter(|&index| !self.is_lazy(&node.node_type, index))
+                    .find_map(|index| self.failed_input(node, index).or_else(|| self.invalid_input(node, index)));
+                let result = match upstream_failure {
+                    Some(failure) => Err(failure),
+                    None => {
+                        // Lazy sockets are left empty; the logic fetches them through `request`
+                        let input_values = (0..node.inputs.len())
+                            .map(|index| if self.is_lazy(&node.node_type, index) { Vec::new() } else { self.read_input(node, index) })
+                            .collect();
+                        self.compute_logic(node, input_values)
+                    }
+                };
+                self.cache.insert(node_id.to_string(), result.clone());
+                result
+            }
+        };
+
+        // One value per output socket. Always produce at least 1 output in trace
+        // (outputNode has no sockets but reports its displayed value here for the UI).
+        let (mut trace_outputs, error) = match result {
+            Ok(outputs) => (outputs, None),
+            Err(failure) => (Vec::new(), Some(failure)),
+        };
+        if trace_outputs.is_empty() {
+            trace_outputs.push(SerdeValue::Null);
+        }
+
+        // Inputs as the logic saw them (lazy branches that were never requested show as empty)
+        let display_inputs = (0..node.inputs.len())
+            .map(|index| self.read_input(node, index).into_iter().next())
+            .collect();
+
+        self.trace.insert(node_id.to_string(), NodeCache {
+            inputs: display_inputs,
+            outputs: trace_outputs,
+            error,
+        });
+        Ok(())
+    }
+
+    fn compute_logic(&mut self, node: &'a Node, input_values: Vec<Vec<SerdeValue>>) -> NodeResult {
+         let failure = |err: EngineError| NodeError {
+             code: err.code(),
+             message: match err {
+                 EngineError::LogicFailed { message, .. } => message,
+                 other => other.to_string(),
+             },
+             origin: node.id.clone(),
+             upstream_failed: false,
+         };
+         let logic_failed = |message: String| failure(EngineError::LogicFailed { node_id: node.id.clone(), message });
+
+         if let Some(group) = group::as_group(node).or_else(|| self.graph_types.get(&node.node_type)) {
+             return self.eval_group(node, group, input_values);
+         }
+         if let Some(body) = node.group.as_deref().filter(|_| iteration::is_loop_type(&node.node_type)) {
+             return self.eval_loop(node, body, input_values);
+         }
+         if functions::is_higher_order(&node.node_type) {
+             return self.eval_higher_order(node, input_values).map_err(logic_failed);
+         }
+
+         // Lookup logic closure from registry
+         let registry = self.registry;
+         let Some(def) = registry.get(&node.node_type) else {
+             return Err(failure(EngineError::UnknownNodeType {
+                 node_type: node.node_type.clone(),
+                 node_id: Some(node.id.clone()),
+             }));
+         };
+
+         self.lazy_failure = None;
+         let numeric = self.numeric;
+         let result = {
+             let fetch = |index: usize| {
+                 self.eval_lazy_input(node, index).map_err(|e| e.to_string())
+             };
+             let inputs = definitions::LogicInputs::new(input_values, Box::new(fetch), numeric);
+             (def.logic)(&inputs, &node.data)
+         };
+
+         match result {
+             Ok(vals) if vals.len() < def.schema.io.outputs.len() => Err(logic_failed(format!(
+                 "Logic for node type {} returned {} value(s) for {} output(s)",
+                 node.node_type, vals.len(), def.schema.io.outputs.len()
+             ))),
+             Ok(vals) => Ok(vals),
+             // The logic bailed out because a lazy input it requested had failed
+             Err(_) if self.lazy_failure.is_some() => Err(self.lazy_failure.take().unwrap()),
+             Err(e) => Err(logic_failed(e)),
+         }
+    }
+
+    /// Runs a group's inner graph on the group's input values.
+    /// A failure inside is reported on the group node itself.
+    fn eval_group(&self, node: &Node, group: &GroupGraph, input_values: Vec<Vec<SerdeValue>>) -> NodeResult {
+        let inputs = input_values.into_iter()
+            .map(|values| values.into_iter().next().unwrap_or(SerdeValue::Null))
+            .collect();
+        self.run_body(group, inputs).map_err(|inner| NodeError {
+            message: format!("{} (inner node `{}`)", inner.message, inner.origin),
+            origin: node.id.clone(),
+            upstream_failed: false,
+            ..inner
+        })
+    }
+
+    /// Runs a loop node's body once per iteration, threading the state through
+    /// (see `iteration`). Unconnected inputs fall back to the data slots.
+    fn eval_loop(&self, node: &Node, body: &GroupGraph, input_values: Vec<Vec<SerdeValue>>) -> NodeResult {
+        let failure = |err: EngineError| NodeError {
+            code: err.code(),
+            message: match err {
+                EngineError::LogicFailed { message, .. } => message,
+                other => other.to_string(),
+            },
+            origin: node.id.clone(),
+            upstream_failed: false,
+        };
+        let inputs: Vec<SerdeValue> = input_values.into_iter().enumerate()
+            .map(|(index, values)| values.into_iter().next().or_else(|| node.data.get(index).cloned()).unwrap_or(SerdeValue::Null))
+            .collect();
+
+        let count = iteration::iteration_count(&node.node_type, &inputs)
+            .map_err(|message| failure(EngineError::LogicFailed { node_id: node.id.clone(), message }))?;
+        if count > self.iteration_limit {
+            return Err(failure(EngineError::IterationLimit { node_id: node.id.clone(), limit: self.iteration_limit }));
+        }
+
+        let collect = node.node_type == iteration::FOR_EACH_NODE;
+        let mut state = inputs.get(1).cloned().unwrap_or(SerdeValue::Null);
+        let mut results = Vec::new();
+        for index in 0..count {
+            let body_inputs = iteration::body_inputs(&node.node_type, body, &inputs, &state, index);
+            let value = self.run_body(body, body_inputs)
+                .map_err(|inner| NodeError {
+                    message: format!("{} (iteration {}, inner node `{}`)", inner.message, index, inner.origin),
+                    origin: node.id.clone(),
+                    upstream_failed: false,
+                    ..inner
+                })?
+                .into_iter().next().unwrap_or(SerdeValue::Null);
+            if collect {
+                results.push(value);
+            } else {
+                state = value;
+            }
+        }
+        Ok(vec![if collect { SerdeValue::Array(results) } else { state }])
+    }
+
+    /// Map / filter / reduce: applies the function input to each list item.
+    /// Unconnected inputs fall back to the data slots.
+    fn eval_higher_order(&self, node: &Node, input_values: Vec<Vec<SerdeValue>>) -> Result<Vec<SerdeValue>, String> {
+        let mut inputs = input_values.into_iter().enumerate()
+            .map(|(index, values)| values.into_iter().next().or_else(|| node.data.get(index).cloned()).unwrap_or(SerdeValue::Null));
+        let list = match inputs.next() {
+            Some(SerdeValue::Array(items)) => items,
+            other => return Err(format!("Expected a list, got {}", other.unwrap_or(SerdeValue::Null))),
+        };
+        let function = inputs.next().unwrap_or(SerdeValue::Null);
+        let Some(name) = functions::function_name(&function) else {
+            return Err(format!("Expected a function, got {}", function));
+        };
+        if !self.graph_types.contains_key(name) && !self.registry.contains_key(name) {
+            return Err(EngineError::UnknownNodeType { node_type: name.to_string(), node_id: None }.to_string());
+        }
+        if list.len() > self.iteration_limit {
+            return Err(EngineError::IterationLimit { node_id: node.id.clone(), limit: self.iteration_limit }.to_string());
+        }
+
+        let in_item = |index: usize| move |message: String| format!("{} (item {})", message, index);
+        match node.node_type.as_str() {
+            functions::LIST_MAP_NODE => {
+                let mapped = list.into_iter().enumerate()
+                    .map(|(index, item)| self.apply_function(name, vec![item]).map_err(in_item(index)))
+                    .collect::<Result<_, _>>()?;
+                Ok(vec![SerdeValue::Array(mapped)])
+            }
+            functions::LIST_FILTER_NODE => {
+                let mut kept = Vec::new();
+                for (index, item) in list.into_iter().enumerate() {
+                    match self.apply_function(name, vec![item.clone()]).map_err(in_item(index))? {
+                        SerdeValue::Bool(true) => kept.push(item),
+                        SerdeValue::Bool(false) => {}
+                        other => return Err(in_item(index)(format!("Filter function must return a boolean, got {}", other))),
+                    }
+                }
+                Ok(vec![SerdeValue::Array(kept)])
+            }
+            _ => {
+                let mut accumulator = inputs.next().unwrap_or(SerdeValue::Null);
+                for (index, item) in list.into_iter().enumerate() {
+                    accumulator = self.apply_function(name, vec![accumulator, item]).map_err(in_item(index))?;
+                }
+                Ok(vec![accumulator])
+            }
+        }
+    }
+
+    /// Calls node type `name` as a function: `args` go into its first input
+    /// sockets, the rest of its inputs fall back to the type's default data,
+    /// and its first output is returned.
+    fn apply_function(&self, name: &str, args: Vec<SerdeValue>) -> Result<SerdeValue, String> {
+        if let Some(graph) = self.graph_types.get(name) {
+            return self.run_body(graph, args)
+                .map(|outputs| outputs.into_iter().next().unwrap_or(SerdeValue::Null))
+                .map_err(|inner| format!("{} (inner node `{}`)", inner.message, inner.origin));
+        }
+        // These need a graph around them and cannot run from a bare function value
+        if name == group::GROUP_NODE || iteration::is_loop_type(name) || functions::is_higher_order(name) {
+            return Err(format!("Node type {} cannot be used as a function", name));
+        }
+        let Some(def) = self.registry.get(name) else {
+            return Err(EngineError::UnknownNodeType { node_type: name.to_string(), node_id: None }.to_string());
+        };
+
+        let mut args = args.into_iter();
+        let values = def.schema.io.inputs.iter()
+            .map(|socket| args.next().map(|arg| definitions::coerce_value(arg, &socket.val_type)).into_iter().collect())
+            .collect();
+        let data: Vec<SerdeValue> = def.schema.data.iter().map(|slot| slot.default_value.clone()).collect();
+        let inputs = definitions::LogicInputs::new(values, Box::new(|_| Ok(Vec::new())), self.numeric);
+        let outputs = (def.logic)(&inputs, &data)?;
+        Ok(outputs.into_iter().next().unwrap_or(SerdeValue::Null))
+    }
+
+    /// Evaluates an inner graph with its input interface nodes preset to
+    /// `inputs`, returning one value per output interface node, or the error
+    /// of the inner node that failed.
+    fn run_body(&self, body: &GroupGraph, inputs: Vec<SerdeValue>) -> Result<Vec<SerdeValue>, NodeError> {
+        let mut inner_cache: HashMap<String, NodeResult> = body.inputs.iter()
+            .zip(inputs)
+            .map(|(id, value)| (id.clone(), Ok(vec![value])))
+            .collect();
+        let mut inner = Evaluator {
+            nodes: &body.nodes,
+            registry: self.registry,
+            graph_types: self.graph_types,
+            iteration_limit: self.iteration_limit,
+            numeric: self.numeric,
+            cache: &mut inner_cache,
+            trace: HashMap::new(),
+            lazy_failure: None,
+        };
+
+        let mut outputs = Vec::with_capacity(body.outputs.len());
+        for output_id in &body.outputs {
+            if let Err(err) = inner.run(output_id) {
+                return Err(NodeError { code: err.code(), message: err.to_string(), origin: output_id.clone(), upstream_failed: false });
+            }
+            match inner.cache.get(output_id) {
+                Some(Ok(values)) => outputs.push(values.first().cloned().unwrap_or(SerdeValue::Null)),
+                Some(Err(failure)) => return Err(failure.clone()),
+                None => outputs.push(SerdeValue::Null),
+            }
+        }
+        Ok(outputs)
+    }
+
+    /// Evaluates whatever is connected to a lazy input socket and returns its values.
+    fn eval_lazy_input(&mut self, node: &Node, index: usize) -> Result<Vec<SerdeValue>, EngineError> {
+        if let Some(socket) = node.inputs.get(index) {
+            for conn in socket {
+                self.run(&conn.id)?;
+            }
+        }
+        if let Some(failure) = self.failed_input(node, index) {
+            let err = EngineError::LogicFailed { node_id: failure.origin.clone(), message: failure.message.clone() };
+            self.lazy_failure = Some(failure);
+            return Err(err);
+        }
+        Ok(self.read_input(node, index))
+    }
+
+    /// The error of the first failed node connected to an input socket, marked as upstream.
+    fn failed_input(&self, node: &Node, index: usize) -> Option<NodeError> {
+        node.inputs.get(index).into_iter().flatten()
+            .find_map(|conn| match self.cache.get(&conn.id) {
+                Some(Err(failure)) => Some(NodeError { upstream_failed: true, ..failure.clone() }),
+                _ => None,
+            })
+    }
+
+    /// Cached values connected to an input socket, after coercion to the socket type.
+    /// Connections whose source has not been evaluated are skipped.
+    /// A value that cannot go into a number socket (e.g. a string or null
+    /// coming from an "any" output) fails the node instead of reading as 0.
+    fn invalid_input(&self, node: &Node, index: usize) -> Option<NodeError> {
+        let socket = definitions::find_socket(self.registry, &node.node_type, true, index)?;
+        if socket.val_type != "number" {
+            return None;
+        }
+        let value = self.read_input(node, index).into_iter().find(|value| numeric::as_number(value).is_none())?;
+        let err = EngineError::InvalidInputValue { node_id: node.id.clone(), index, expected: socket.val_type.clone(), value: value.to_string() };
+        Some(NodeError { code: err.code(), message: err.to_string(), origin: node.id.clone(), upstream_failed: false })
+    }
+
+    fn read_input(&self, node: &Node, index: usize) -> Vec<SerdeValue> {
+        let input_type = definitions::find_socket(self.registry, &node.node_type, true, index)
+            .map(|s| s.val_type.as_str())
+            .unwrap_or("any");
+        node.inputs.get(index).into_iter().flatten()
+            .filter_map(|conn| match self.cache.get(&conn.id) {
+                Some(Ok(outs)) => outs.get(conn.output_index),
+                _ => None,
+            })
+            .map(|val| definitions::coerce_value(val.clone(), input_type))
+            .collect()
+    }
+
+    fn is_lazy(&self, node_type: &str, index: usize) -> bool {
+        definitions::find_socket(self.registry, node_type, true, index).is_some_and(|s| s.lazy)
+    }
+
+    /// Topological order (dependencies first) of `id` and every node upstream of it,
+    /// computed with an explicit stack so long chains can't overflow the wasm stack.
+    /// Lazy input sockets are not followed; they are evaluated on request.
+    fn upstream_order(&self, id: &str) -> Result<Vec<String>, EngineError> {
+        let mut order = Vec::new();
+        let mut visited: HashSet<&str> = HashSet::new();
+        // (node, children pushed?) - a node is emitted once all its inputs have been.
+        let mut stack: Vec<(&str, bool)> = vec![(id, false)];
+
+        while let Some((current, expanded)) = stack.pop() {
+            if expanded {
+                order.push(current.to_string());
+                continue;
+            }
+            if !visited.insert(current) {
+                continue;
+            }
+            let node = self.nodes.get(current).ok_or_else(|| EngineError::NodeNotFound { node_id: current.to_string() })?;
+            stack.push((current, true));
+            // A cached node needs none of its inputs recomputed, but they are
+            // still visited so the trace covers the whole upstream subgraph.
+            for (index, socket) in node.inputs.iter().enumerate().rev() {
+                if self.is_lazy(&node.node_type, index) {
+                    continue;
+                }
+                for conn in socket.iter().rev() {
+                    if !visited.contains(conn.id.as_str()) {
+                        stack.push((conn.id.as_str(), false));
+                    }
+                }
+            }
+        }
+
+        Ok(order)
+    }
+}
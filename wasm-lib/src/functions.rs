@@ -0,0 +1,80 @@
+use serde_json::Value as SerdeValue;
+
+// --- Function Values ---
+
+// A "function" value names a node type (built-in or registered with
+// `register_graph_node_type`). The map / filter / reduce list nodes apply it
+// to each element: the element (and accumulator, for reduce) go into its
+// first input sockets, the remaining inputs fall back to the type's default
+// data, and its first output is the result.
+pub const FUNCTION_NODE: &str = "functionNode";
+pub const LIST_MAP_NODE: &str = "listMapNode";
+pub const LIST_FILTER_NODE: &str = "listFilterNode";
+pub const LIST_REDUCE_NODE: &str = "listReduceNode";
+
+pub fn is_higher_order(node_type: &str) -> bool {
+    matches!(node_type, LIST_MAP_NODE | LIST_FILTER_NODE | LIST_REDUCE_NODE)
+}
+
+// Serialized as `{ "function": "<node type>" }`
+pub fn function_value(node_type: &str) -> SerdeValue {
+    serde_json::json!({ "function": node_type })
+}
+
+pub fn function_name(value: &SerdeValue) -> Option<&str> {
+    value.as_object()?.get("function")?.as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::GraphEngine;
+    use crate::test_util::{add_with, connect, error, value};
+    use serde_json::json;
+
+    // A `higher_order` node applying `node_type`, with `data` in its slots
+    fn apply(engine: &mut GraphEngine, higher_order: &str, node_type: &str, data: Vec<SerdeValue>) -> String {
+        let id = format!("{}-{}", higher_order, engine.nodes().count());
+        let function_id = format!("{}-function", id);
+        add_with(engine, &function_id, FUNCTION_NODE, vec![json!(node_type)]);
+        add_with(engine, &id, higher_order, data);
+        connect(engine, &function_id, 0, &id, 1);
+        id
+    }
+
+    #[test]
+    fn function_values_name_a_node_type() {
+        let value = function_value("sqrtNode");
+        assert_eq!(value, json!({ "function": "sqrtNode" }));
+        assert_eq!(function_name(&value), Some("sqrtNode"));
+        assert_eq!(function_name(&json!({ "name": "x" })), None);
+    }
+
+    #[test]
+    fn map_filter_and_reduce_apply_the_function() {
+        let mut engine = GraphEngine::new();
+        let map = apply(&mut engine, LIST_MAP_NODE, "absNode", vec![json!([-1, 2, -3])]);
+        assert_eq!(value(&mut engine, &map), json!([1, 2, 3]));
+
+        let filter = apply(&mut engine, LIST_FILTER_NODE, "notNode", vec![json!([true, false, false])]);
+        assert_eq!(value(&mut engine, &filter), json!([false, false]));
+
+        let reduce = apply(&mut engine, LIST_REDUCE_NODE, "additionNode", vec![json!([1, 2, 3]), SerdeValue::Null, json!(10)]);
+        assert_eq!(value(&mut engine, &reduce), json!(16));
+    }
+
+    #[test]
+    fn failures_name_the_item() {
+        let mut engine = GraphEngine::new();
+        let map = apply(&mut engine, LIST_MAP_NODE, "absNode", vec![json!([1, "x"])]);
+        let failure = error(&mut engine, &map);
+        assert!(failure.message.contains("item 1"), "{}", failure.message);
+
+        let unknown = apply(&mut engine, LIST_MAP_NODE, "noSuchNode", vec![json!([1])]);
+        assert!(error(&mut engine, &unknown).message.contains("noSuchNode"));
+
+        engine.set_iteration_limit(2);
+        let long = apply(&mut engine, LIST_MAP_NODE, "absNode", vec![json!([1, 2, 3])]);
+        assert!(error(&mut engine, &long).message.contains("iterations"));
+    }
+}
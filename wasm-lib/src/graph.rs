@@ -0,0 +1,152 @@
+use crate::group::GroupGraph;
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// We need a way to represent the "Any" type from TS.
+// SerdeValue can hold any JSON-serializable data.
+use serde_json::Value as SerdeValue;
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InputConnection {
+    pub id: String,
+    pub output_index: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OutputConnection {
+    pub id: String,
+    pub input_index: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Node {
+    pub id: String,
+    pub node_type: String,
+    pub inputs: Vec<Vec<InputConnection>>,
+    pub outputs: Vec<Vec<OutputConnection>>,
+    pub data: Vec<SerdeValue>,
+    #[serde(default)]
+    pub ui: Option<SerdeValue>, // Frontend metadata, only stored for saving
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<Box<GroupGraph>>, // Inner graph, for group nodes only
+}
+
+impl Node {
+    /// Every edge touching this node, inputs first, in connection order.
+    pub fn edges(&self) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        for (target_input_index, socket) in self.inputs.iter().enumerate() {
+            for conn in socket {
+                edges.push(Edge {
+                    source_id: conn.id.clone(),
+                    source_output_index: conn.output_index,
+                    target_id: self.id.clone(),
+                    target_input_index,
+                });
+            }
+        }
+        for (source_output_index, socket) in self.outputs.iter().enumerate() {
+            for conn in socket {
+                edges.push(Edge {
+                    source_id: self.id.clone(),
+                    source_output_index,
+                    target_id: conn.id.clone(),
+                    target_input_index: conn.input_index,
+                });
+            }
+        }
+        edges
+    }
+}
+
+/// A single connection between an output socket and an input socket.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Edge {
+    #[serde(rename = "sourceId")]
+    pub source_id: String,
+    #[serde(rename = "sourceOutputIndex")]
+    pub source_output_index: usize,
+    #[serde(rename = "targetId")]
+    pub target_id: String,
+    #[serde(rename = "targetInputIndex")]
+    pub target_input_index: usize,
+}
+
+/// Where an edge sits in its source's output connections and in its target's
+/// input connections. Undo puts removed edges back there, keeping the order.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct EdgePosition {
+    #[serde(rename = "sourcePosition")]
+    pub source: usize,
+    #[serde(rename = "targetPosition")]
+    pub target: usize,
+}
+
+/// Position of an existing edge, if both halves are present.
+pub(crate) fn edge_position(nodes: &HashMap<String, Node>, edge: &Edge) -> Option<EdgePosition> {
+    let source = nodes.get(&edge.source_id)?.outputs.get(edge.source_output_index)?
+        .iter().position(|c| c.id == edge.target_id && c.input_index == edge.target_input_index)?;
+    let target = nodes.get(&edge.target_id)?.inputs.get(edge.target_input_index)?
+        .iter().position(|c| c.id == edge.source_id && c.output_index == edge.source_output_index)?;
+    Some(EdgePosition { source, target })
+}
+
+/// Adds both halves of an edge without any checks (sockets must exist).
+pub(crate) fn attach_edge(nodes: &mut HashMap<String, Node>, edge: &Edge) {
+    attach_edge_at(nodes, edge, None);
+}
+
+/// Like `attach_edge`, inserting at `position` (clamped to the current
+/// connection counts) instead of appending.
+pub(crate) fn attach_edge_at(nodes: &mut HashMap<String, Node>, edge: &Edge, position: Option<EdgePosition>) {
+    if let Some(source) = nodes.get_mut(&edge.source_id) {
+        let socket = &mut source.outputs[edge.source_output_index];
+        let index = position.map_or(socket.len(), |p| p.source.min(socket.len()));
+        socket.insert(index, OutputConnection {
+            id: edge.target_id.clone(),
+            input_index: edge.target_input_index,
+        });
+    }
+    if let Some(target) = nodes.get_mut(&edge.target_id) {
+        let socket = &mut target.inputs[edge.target_input_index];
+        let index = position.map_or(socket.len(), |p| p.target.min(socket.len()));
+        socket.insert(index, InputConnection {
+            id: edge.source_id.clone(),
+            output_index: edge.source_output_index,
+        });
+    }
+}
+
+/// Removes both halves of an edge (source output and target input), if present.
+pub(crate) fn detach_edge(nodes: &mut HashMap<String, Node>, edge: &Edge) {
+    if let Some(source_node) = nodes.get_mut(&edge.source_id) {
+         if let Some(socket) = source_node.outputs.get_mut(edge.source_output_index) {
+             socket.retain(|c| !(c.id == edge.target_id && c.input_index == edge.target_input_index));
+         }
+    }
+    if let Some(target_node) = nodes.get_mut(&edge.target_id) {
+         if let Some(socket) = target_node.inputs.get_mut(edge.target_input_index) {
+             socket.retain(|c| !(c.id == edge.source_id && c.output_index == edge.source_output_index));
+         }
+    }
+}
+
+/// Result of `add_edge`: nodes whose cached values were invalidated, plus any
+/// edges that were dropped to respect a socket's `max_connections`.
+#[derive(Serialize, Clone, Debug)]
+pub struct EdgeUpdate {
+    pub dirty: Vec<String>,
+    pub removed: Vec<Edge>,
+}
+
+/// What `add_edge` does when a socket already has `max_connections` edges.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionPolicy {
+    /// Refuse the new edge with an error.
+    Reject,
+    /// Drop the oldest existing edge(s) on the full socket.
+    Replace,
+}
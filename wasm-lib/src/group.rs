@@ -0,0 +1,235 @@
+use crate::definitions::{NodeSchema, SocketDef, SocketUI, IO};
+use crate::graph::Node;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// --- Group Nodes ---
+
+// A group is a `groupNode` whose `Node::group` holds an inner graph. Each
+// `groupInputNode` inside becomes an input socket of the group and each
+// `groupOutputNode` an output socket; from the outside the group evaluates
+// like any other node.
+pub const GROUP_NODE: &str = "groupNode";
+pub const GROUP_INPUT_NODE: &str = "groupInputNode";
+pub const GROUP_OUTPUT_NODE: &str = "groupOutputNode";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GroupGraph {
+    pub nodes: HashMap<String, Node>,
+    // Interface node IDs, in socket order
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+impl GroupGraph {
+    /// Schema of the group as seen from outside, derived from its interface nodes.
+    pub fn schema(&self) -> NodeSchema {
+        let socket = |id: &String, index: usize, is_input: bool| SocketDef {
+            name: self.nodes.get(id).map_or_else(|| default_socket_name(index, is_input), |node| interface_name(node, index, is_input)),
+            val_type: "any".to_string(),
+            ui: SocketUI { ui_type: "none".to_string(), show_name: true },
+            max_connections: if is_input { 1 } else { 9999 },
+            lazy: false,
+        };
+        NodeSchema {
+            name: "Group".to_string(),
+            version: 1,
+            io: IO {
+                inputs: self.inputs.iter().enumerate().map(|(i, id)| socket(id, i, true)).collect(),
+                outputs: self.outputs.iter().enumerate().map(|(i, id)| socket(id, i, false)).collect(),
+            },
+            data: Vec::new(),
+            auto_evaluate_on_connect: false,
+        }
+    }
+
+    /// Brings `inputs` / `outputs` in line with the interface nodes actually in
+    /// the graph: removed ones are dropped, new ones appended (sorted by ID).
+    /// Returns, per old socket index, its new index (None if it is gone).
+    pub fn refresh_interface(&mut self) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+        let inputs = refresh_sockets(&self.nodes, &mut self.inputs, GROUP_INPUT_NODE);
+        let outputs = refresh_sockets(&self.nodes, &mut self.outputs, GROUP_OUTPUT_NODE);
+        (inputs, outputs)
+    }
+
+    pub fn is_interface(&self, id: &str) -> bool {
+        self.inputs.iter().chain(&self.outputs).any(|interface_id| interface_id == id)
+    }
+}
+
+/// The inner graph of a group node. Loop nodes also keep their body in
+/// `Node::group`, but their sockets are fixed by their schema instead.
+pub fn as_group(node: &Node) -> Option<&GroupGraph> {
+    node.group.as_deref().filter(|_| node.node_type == GROUP_NODE)
+}
+
+fn refresh_sockets(nodes: &HashMap<String, Node>, ids: &mut Vec<String>, node_type: &str) -> Vec<Option<usize>> {
+    let old = std::mem::take(ids);
+    let mut index_map = Vec::with_capacity(old.len());
+    for id in old {
+        if nodes.get(&id).is_some_and(|node| node.node_type == node_type) && !ids.contains(&id) {
+            index_map.push(Some(ids.len()));
+            ids.push(id);
+        } else {
+            index_map.push(None);
+        }
+    }
+    let mut added: Vec<&String> = nodes.values()
+        .filter(|node| node.node_type == node_type && !ids.contains(&node.id))
+        .map(|node| &node.id)
+        .collect();
+    added.sort();
+    ids.extend(added.into_iter().cloned());
+    index_map
+}
+
+// Interface nodes keep their socket name in data slot 0
+fn interface_name(node: &Node, index: usize, is_input: bool) -> String {
+    match node.data.first().and_then(|name| name.as_str()) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => default_socket_name(index, is_input),
+    }
+}
+
+fn default_socket_name(index: usize, is_input: bool) -> String {
+    format!("{} {}", if is_input { "in" } else { "out" }, index + 1)
+}
+
+/// An ID not yet used in `nodes` (nor rejected by `taken`), starting from `base`.
+pub(crate) fn unique_id(nodes: &HashMap<String, Node>, base: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut id = base.to_string();
+    let mut n = 1;
+    while nodes.contains_key(&id) || taken(&id) {
+        n += 1;
+        id = format!("{}-{}", base, n);
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::GraphEngine;
+    use crate::test_util::{add, add_with, connect, value};
+    use serde_json::json;
+
+    // a, b -> sum -> double -> out, with sum and double grouped as `g`
+    fn grouped() -> GraphEngine {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "a", "numberNode", vec![json!(2)]);
+        add_with(&mut engine, "b", "numberNode", vec![json!(3)]);
+        add(&mut engine, "sum", "additionNode");
+        add(&mut engine, "double", "additionNode");
+        add(&mut engine, "out", "outputNode");
+        connect(&mut engine, "a", 0, "sum", 0);
+        connect(&mut engine, "b", 0, "sum", 1);
+        connect(&mut engine, "sum", 0, "double", 0);
+        connect(&mut engine, "sum", 0, "double", 1);
+        connect(&mut engine, "double", 0, "out", 0);
+        engine.group_nodes("g".into(), vec!["sum".into(), "double".into()]).unwrap();
+        engine
+    }
+
+    #[test]
+    fn grouping_routes_edges_through_interface_sockets() {
+        let mut engine = grouped();
+        let schema = engine.node_schema("g").unwrap();
+        assert_eq!(schema.io.inputs.len(), 2);
+        assert_eq!(schema.io.outputs.len(), 1);
+        assert!(engine.node("sum").is_none());
+        assert_eq!(value(&mut engine, "out"), json!(10));
+
+        engine.update_node_data("a", vec![json!(4)]).unwrap();
+        assert_eq!(value(&mut engine, "out"), json!(14));
+    }
+
+    #[test]
+    fn ungroup_restores_the_flat_graph() {
+        let mut engine = grouped();
+        let update = engine.ungroup("g").unwrap();
+        assert!(update.removed.is_empty());
+        assert!(engine.node("g").is_none());
+        assert_eq!(engine.node("double").unwrap().inputs[0][0].id, "sum");
+        assert_eq!(value(&mut engine, "out"), json!(10));
+
+        engine.undo().unwrap();
+        assert!(engine.node("g").is_some());
+        assert_eq!(value(&mut engine, "out"), json!(10));
+        assert_eq!(engine.ungroup("a").unwrap_err().code(), "NOT_A_GROUP");
+    }
+
+    #[test]
+    fn grouping_refuses_selections_that_would_loop() {
+        let mut engine = GraphEngine::new();
+        for id in ["x", "y", "z"] {
+            add(&mut engine, id, "additionNode");
+        }
+        connect(&mut engine, "x", 0, "y", 0);
+        connect(&mut engine, "y", 0, "z", 0);
+        let err = engine.group_nodes("g".into(), vec!["x".into(), "z".into()]).unwrap_err();
+        assert_eq!(err.code(), "CYCLE");
+        assert!(engine.node("x").is_some());
+    }
+
+    #[test]
+    fn entered_groups_are_edited_in_place() {
+        let mut engine = grouped();
+        engine.enter_group("g").unwrap();
+        assert_eq!(engine.scope(), ["g"]);
+        assert!(engine.node("sum").is_some());
+        engine.update_node_data("double", vec![json!(0), json!(0)]).unwrap();
+        engine.remove_edge("sum".into(), 0, "double".into(), 1).unwrap();
+        engine.update_node_data("double", vec![json!(0), json!(100)]).unwrap();
+
+        let update = engine.exit_group().unwrap();
+        assert!(update.removed.is_empty());
+        assert!(engine.scope().is_empty());
+        assert_eq!(value(&mut engine, "out"), json!(105));
+        assert!(engine.exit_group().is_none());
+    }
+
+    #[test]
+    fn inner_nodes_see_the_values_fed_into_the_group() {
+        let mut engine = grouped();
+        engine.enter_group("g").unwrap();
+        assert_eq!(value(&mut engine, "sum"), json!(5));
+        // Still there after the input node itself is invalidated
+        let input = engine.node("sum").unwrap().inputs[0][0].id.clone();
+        engine.update_node_data(&input, vec![json!("x")]).unwrap();
+        assert_eq!(value(&mut engine, "double"), json!(10));
+    }
+
+    #[test]
+    fn editing_inside_is_one_undo_step_outside() {
+        let mut engine = grouped();
+        engine.enter_group("g").unwrap();
+        let input = engine.node("sum").unwrap().inputs[1][0].id.clone();
+        engine.remove_node(&input).unwrap();
+        engine.update_node_data("sum", vec![json!(0), json!(1)]).unwrap();
+        let update = engine.exit_group().unwrap();
+        assert_eq!(update.removed.len(), 1);
+        assert_eq!(engine.node_schema("g").unwrap().io.inputs.len(), 1);
+        assert_eq!(value(&mut engine, "out"), json!(6));
+
+        engine.undo().unwrap().unwrap();
+        assert_eq!(engine.node_schema("g").unwrap().io.inputs.len(), 2);
+        assert_eq!(engine.node("b").unwrap().outputs[0][0].id, "g");
+        assert_eq!(value(&mut engine, "out"), json!(10));
+        engine.redo().unwrap().unwrap();
+        assert_eq!(value(&mut engine, "out"), json!(6));
+
+        // The grouping before it is still undoable
+        engine.undo().unwrap();
+        engine.undo().unwrap();
+        assert!(engine.node("g").is_none());
+        assert_eq!(value(&mut engine, "out"), json!(10));
+    }
+
+    #[test]
+    fn groups_survive_export_and_import() {
+        let engine = grouped();
+        let doc = engine.export_graph();
+        let mut copy = GraphEngine::new();
+        copy.import_graph(doc).unwrap();
+        assert_eq!(value(&mut copy, "out"), json!(10));
+    }
+}
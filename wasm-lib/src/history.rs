@@ -0,0 +1,299 @@
+use crate::graph::{Edge, EdgePosition};
+use crate::group::GroupGraph;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as SerdeValue;
+
+// --- Undo / Redo ---
+
+// Oldest steps are dropped beyond this
+const MAX_HISTORY: usize = 200;
+
+// A structural change to the graph. History stores these both ways (to redo
+// and to undo a step), they are reported back so the UI can re-sync, and
+// `apply_batch` takes a list of them.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind")]
+pub enum GraphOp {
+    #[serde(rename = "addNode")]
+    AddNode {
+        id: String,
+        #[serde(rename = "type")]
+        node_type: String,
+        #[serde(default)]
+        data: Vec<SerdeValue>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ui: Option<SerdeValue>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<Box<GroupGraph>>,
+    },
+    #[serde(rename = "removeNode")]
+    RemoveNode { id: String },
+    #[serde(rename = "addEdge")]
+    AddEdge(Edge),
+    #[serde(rename = "removeEdge")]
+    RemoveEdge(Edge),
+    // Re-adds a removed edge where it was in both connection lists (recorded by undo)
+    #[serde(rename = "restoreEdge")]
+    RestoreEdge {
+        #[serde(flatten)]
+        edge: Edge,
+        #[serde(flatten)]
+        position: EdgePosition,
+    },
+    #[serde(rename = "setData")]
+    SetData { id: String, data: Vec<SerdeValue> },
+    #[serde(rename = "groupNodes")]
+    GroupNodes {
+        #[serde(rename = "groupId")]
+        group_id: String,
+        ids: Vec<String>,
+    },
+    #[serde(rename = "ungroup")]
+    Ungroup { id: String },
+}
+
+// One undo step: the ops that redo it, and the ops (in application order) that revert it
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub label: Option<String>,
+    pub redo: Vec<GraphOp>,
+    pub undo: Vec<GraphOp>,
+}
+
+// Returned by `undo` / `redo`
+#[derive(Serialize, Clone, Debug)]
+pub struct HistoryUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub dirty: Vec<String>,
+    pub changes: Vec<GraphOp>,
+}
+
+#[derive(Default, Clone)]
+pub struct History {
+    undo_stack: Vec<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+    // Open `begin_group` calls; records are merged into the top entry while > 0
+    group_depth: usize,
+    // Set while undo/redo replays ops, so the replay itself isn't recorded
+    replaying: bool,
+}
+
+impl History {
+    // Records one mutation. `undo` must revert `redo` when applied in order.
+    pub fn record(&mut self, redo: Vec<GraphOp>, undo: Vec<GraphOp>) {
+        if self.replaying || redo.is_empty() {
+            return;
+        }
+        self.redo_stack.clear();
+        if self.group_depth > 0 {
+            if let Some(group) = self.undo_stack.last_mut() {
+                group.redo.extend(redo);
+                // Later ops must be reverted first
+                let mut merged = undo;
+                merged.append(&mut group.undo);
+                group.undo = merged;
+                return;
+            }
+        }
+        self.push(HistoryEntry { label: None, redo, undo });
+    }
+
+    // Starts a named group: every mutation until the matching `end_group` is one undo step
+    pub fn begin_group(&mut self, label: String) {
+        if self.group_depth == 0 {
+            self.redo_stack.clear();
+            self.push(HistoryEntry { label: Some(label), redo: Vec::new(), undo: Vec::new() });
+        }
+        self.group_depth += 1;
+    }
+
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+        // Don't leave empty steps behind
+        if self.group_depth == 0 && self.undo_stack.last().is_some_and(|e| e.redo.is_empty()) {
+            self.undo_stack.pop();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = History::default();
+    }
+
+    // Takes the step to undo; hand it back with `finish_undo` once replayed
+    pub fn take_undo(&mut self) -> Option<HistoryEntry> {
+        self.group_depth = 0;
+        let entry = self.undo_stack.pop()?;
+        self.replaying = true;
+        Some(entry)
+    }
+
+    pub fn finish_undo(&mut self, entry: HistoryEntry) {
+        self.replaying = false;
+        self.redo_stack.push(entry);
+    }
+
+    pub fn take_redo(&mut self) -> Option<HistoryEntry> {
+        self.group_depth = 0;
+        let entry = self.redo_stack.pop()?;
+        self.replaying = true;
+        Some(entry)
+    }
+
+    pub fn finish_redo(&mut self, entry: HistoryEntry) {
+        self.replaying = false;
+        self.undo_stack.push(entry);
+    }
+
+    fn push(&mut self, entry: HistoryEntry) {
+        self.undo_stack.push(entry);
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::GraphEngine;
+    use crate::graph::ConnectionPolicy;
+    use crate::test_util::{add, add_with, connect, value};
+    use serde_json::json;
+
+    fn chain() -> GraphEngine {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "a", "numberNode", vec![json!(2)]);
+        add_with(&mut engine, "b", "numberNode", vec![json!(3)]);
+        add(&mut engine, "sum", "additionNode");
+        connect(&mut engine, "a", 0, "sum", 0);
+        connect(&mut engine, "b", 0, "sum", 1);
+        engine.clear_history();
+        engine
+    }
+
+    #[test]
+    fn undo_and_redo_data_edges_and_nodes() {
+        let mut engine = chain();
+        engine.update_node_data("a", vec![json!(10)]).unwrap();
+        assert_eq!(value(&mut engine, "sum"), json!(13));
+
+        let update = engine.undo().unwrap().unwrap();
+        assert!(update.dirty.contains(&"sum".to_string()));
+        assert_eq!(value(&mut engine, "sum"), json!(5));
+        engine.redo().unwrap().unwrap();
+        assert_eq!(value(&mut engine, "sum"), json!(13));
+
+        engine.remove_node("b").unwrap();
+        assert!(engine.node("b").is_none());
+        engine.undo().unwrap();
+        assert_eq!(engine.node("sum").unwrap().inputs[1][0].id, "b");
+        assert_eq!(value(&mut engine, "sum"), json!(13));
+
+        assert!(engine.undo().unwrap().is_some());
+        assert!(engine.undo().unwrap().is_none());
+        assert!(engine.can_redo());
+    }
+
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut engine = chain();
+        engine.update_node_data("a", vec![json!(10)]).unwrap();
+        engine.undo().unwrap();
+        engine.update_node_data("b", vec![json!(1)]).unwrap();
+        assert!(!engine.can_redo());
+    }
+
+    #[test]
+    fn named_groups_undo_as_one_step() {
+        let mut engine = chain();
+        engine.begin_group("Delete".to_string());
+        engine.remove_edge("a".into(), 0, "sum".into(), 0).unwrap();
+        engine.remove_node("a").unwrap();
+        engine.end_group();
+
+        let update = engine.undo().unwrap().unwrap();
+        assert_eq!(update.label.as_deref(), Some("Delete"));
+        assert_eq!(value(&mut engine, "sum"), json!(5));
+        assert!(!engine.can_undo());
+    }
+
+    #[test]
+    fn batches_apply_atomically() {
+        let mut engine = chain();
+        let ops = vec![
+            GraphOp::SetData { id: "a".into(), data: vec![json!(7)] },
+            GraphOp::RemoveEdge(Edge { source_id: "b".into(), source_output_index: 0, target_id: "sum".into(), target_input_index: 1 }),
+        ];
+        let dirty = engine.apply_batch(ops).unwrap();
+        assert!(dirty.contains(&"sum".to_string()));
+        assert_eq!(value(&mut engine, "sum"), json!(7));
+
+        // One step in history
+        engine.undo().unwrap();
+        assert_eq!(value(&mut engine, "sum"), json!(5));
+
+        let failing = vec![
+            GraphOp::SetData { id: "a".into(), data: vec![json!(100)] },
+            GraphOp::RemoveNode { id: "missing".into() },
+        ];
+        assert_eq!(engine.apply_batch(failing).unwrap_err().code(), "NODE_NOT_FOUND");
+        assert_eq!(engine.node("a").unwrap().data, [json!(2)]);
+        assert_eq!(value(&mut engine, "sum"), json!(5));
+        assert!(engine.can_redo());
+    }
+
+    #[test]
+    fn batches_report_added_nodes_as_dirty() {
+        let mut engine = chain();
+        assert_eq!(value(&mut engine, "a"), json!(2));
+        let ops = vec![
+            GraphOp::RemoveNode { id: "a".into() },
+            GraphOp::AddNode { id: "a".into(), node_type: "numberNode".into(), data: vec![json!(4)], ui: None, group: None },
+            GraphOp::AddNode { id: "c".into(), node_type: "numberNode".into(), data: vec![json!(1)], ui: None, group: None },
+        ];
+        let dirty = engine.apply_batch(ops).unwrap();
+        assert!(dirty.contains(&"a".to_string()));
+        assert!(dirty.contains(&"c".to_string()));
+        assert_eq!(value(&mut engine, "a"), json!(4));
+    }
+
+    #[test]
+    fn undo_puts_edges_back_in_their_original_order() {
+        let mut engine = chain();
+        add(&mut engine, "x", "additionNode");
+        add(&mut engine, "y", "additionNode");
+        connect(&mut engine, "a", 0, "x", 0);
+        connect(&mut engine, "a", 0, "y", 0);
+        let order = |engine: &GraphEngine| engine.node("a").unwrap().outputs[0].iter().map(|c| c.id.clone()).collect::<Vec<_>>();
+        assert_eq!(order(&engine), ["sum", "x", "y"]);
+
+        engine.remove_edge("a".into(), 0, "sum".into(), 0).unwrap();
+        engine.undo().unwrap();
+        assert_eq!(order(&engine), ["sum", "x", "y"]);
+
+        engine.remove_node("x").unwrap();
+        engine.undo().unwrap();
+        assert_eq!(order(&engine), ["sum", "x", "y"]);
+
+        // The edge `b -> sum` replaces `a -> sum`; undo restores it in place
+        engine.add_edge("b".into(), 0, "sum".into(), 0, ConnectionPolicy::Replace).unwrap();
+        assert_eq!(order(&engine), ["x", "y"]);
+        engine.undo().unwrap();
+        assert_eq!(order(&engine), ["sum", "x", "y"]);
+        engine.redo().unwrap();
+        engine.undo().unwrap();
+        assert_eq!(order(&engine), ["sum", "x", "y"]);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::graph::{attach_edge, Edge, Node};
+use crate::group::{GroupGraph, GROUP_INPUT_NODE, GROUP_OUTPUT_NODE};
+use crate::numeric;
+use serde_json::Value as SerdeValue;
+
+// --- Iteration Nodes ---
+
+// Loop nodes run a body graph (kept in `Node::group`, edited with
+// `enter_group`) once per iteration instead of feeding back into the outer
+// graph, which must stay acyclic. The body's interface nodes (matched by
+// socket name) carry the loop state in and out:
+//   repeatNode  (count, initial)  body: value, index        -> value
+//   forEachNode (list)            body: item, index         -> result  (collected into a list)
+//   foldNode    (list, initial)   body: accumulator, item, index -> accumulator
+pub const REPEAT_NODE: &str = "repeatNode";
+pub const FOR_EACH_NODE: &str = "forEachNode";
+pub const FOLD_NODE: &str = "foldNode";
+
+// Iterations allowed per loop node evaluation unless changed with `set_iteration_limit`
+pub const DEFAULT_ITERATION_LIMIT: usize = 10_000;
+
+pub fn is_loop_type(node_type: &str) -> bool {
+    matches!(node_type, REPEAT_NODE | FOR_EACH_NODE | FOLD_NODE)
+}
+
+// Socket names of the body's input interface nodes, in `body_inputs` order
+fn body_input_names(node_type: &str) -> &'static [&'static str] {
+    match node_type {
+        REPEAT_NODE => &["value", "index"],
+        FOR_EACH_NODE => &["item", "index"],
+        FOLD_NODE => &["accumulator", "item", "index"],
+        _ => &[],
+    }
+}
+
+/// Body a new loop node starts with: its interface nodes, with the first
+/// input passed straight through to the output.
+pub fn default_body(node_type: &str) -> Option<GroupGraph> {
+    let output = match node_type {
+        REPEAT_NODE => "value",
+        FOR_EACH_NODE => "result",
+        FOLD_NODE => "accumulator",
+        _ => return None,
+    };
+    let inputs = body_input_names(node_type);
+
+    let interface_node = |id: String, node_type: &str, name: &str| Node {
+        inputs: if node_type == GROUP_OUTPUT_NODE { vec![Vec::new()] } else { Vec::new() },
+        outputs: if node_type == GROUP_INPUT_NODE { vec![Vec::new()] } else { Vec::new() },
+        id,
+        node_type: node_type.to_string(),
+        data: vec![SerdeValue::String(name.to_string())],
+        ui: None,
+        group: None,
+    };
+
+    let mut body = GroupGraph::default();
+    for (i, name) in inputs.iter().enumerate() {
+        let id = format!("in-{}", i + 1);
+        body.nodes.insert(id.clone(), interface_node(id.clone(), GROUP_INPUT_NODE, name));
+        body.inputs.push(id);
+    }
+    body.nodes.insert("out-1".to_string(), interface_node("out-1".to_string(), GROUP_OUTPUT_NODE, output));
+    body.outputs.push("out-1".to_string());
+    attach_edge(&mut body.nodes, &Edge {
+        source_id: body.inputs[0].clone(),
+        source_output_index: 0,
+        target_id: "out-1".to_string(),
+        target_input_index: 0,
+    });
+    Some(body)
+}
+
+/// Number of iterations a loop node will run, or an error message for bad inputs.
+pub fn iteration_count(node_type: &str, inputs: &[SerdeValue]) -> Result<usize, String> {
+    let value = inputs.first().unwrap_or(&SerdeValue::Null);
+    if node_type == REPEAT_NODE {
+        return match numeric::as_number(value) {
+            Some(count) if count >= 0.0 && count.fract() == 0.0 => Ok(count as usize),
+            _ => Err(format!("Repeat count must be a non-negative whole number, got {}", value)),
+        };
+    }
+    match value {
+        SerdeValue::Array(items) => Ok(items.len()),
+        other => Err(format!("Expected a list to iterate over, got {}", other)),
+    }
+}
+
+/// Values for the body's input interface nodes (in `body.inputs` order) in
+/// iteration `index`, given the loop node's inputs and the state carried over
+/// from the last iteration. Interface nodes are matched by socket name, so
+/// removing and re-adding one inside the body keeps the binding; a name the
+/// loop doesn't provide gets null.
+pub fn body_inputs(node_type: &str, body: &GroupGraph, inputs: &[SerdeValue], state: &SerdeValue, index: usize) -> Vec<SerdeValue> {
+    let item = || inputs.first().and_then(|list| list.get(index)).cloned().unwrap_or(SerdeValue::Null);
+    let values = match node_type {
+        REPEAT_NODE => vec![state.clone(), serde_json::json!(index)],
+        FOR_EACH_NODE => vec![item(), serde_json::json!(index)],
+        _ => vec![state.clone(), item(), serde_json::json!(index)],
+    };
+    let names = body_input_names(node_type);
+    body.inputs.iter()
+        .map(|id| {
+            let name = body.nodes.get(id).and_then(|node| node.data.first()).and_then(SerdeValue::as_str);
+            names.iter().position(|known| Some(*known) == name)
+                .and_then(|position| values.get(position).cloned())
+                .unwrap_or(SerdeValue::Null)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::GraphEngine;
+    use crate::test_util::{add, add_with, connect, error, value};
+    use serde_json::json;
+
+    // Replaces the pass-through body of loop `id` with `inputs[0] + inputs[1]`
+    // (a constant `operand` when there is no second input to add)
+    fn sum_body(engine: &mut GraphEngine, id: &str, inputs: &[&str], operand: SerdeValue) {
+        engine.enter_group(id).unwrap();
+        engine.remove_edge("in-1".into(), 0, "out-1".into(), 0).unwrap();
+        add_with(engine, "sum", "additionNode", vec![json!(0), operand]);
+        for (socket, input) in inputs.iter().enumerate() {
+            connect(engine, input, 0, "sum", socket);
+        }
+        connect(engine, "sum", 0, "out-1", 0);
+        engine.exit_group().unwrap();
+    }
+
+    #[test]
+    fn repeat_threads_the_value_through_each_iteration() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "loop", REPEAT_NODE, vec![json!(5), json!(10)]);
+        sum_body(&mut engine, "loop", &["in-1"], json!(3));
+        assert_eq!(value(&mut engine, "loop"), json!(25));
+
+        engine.update_node_data("loop", vec![json!(0), json!(10)]).unwrap();
+        assert_eq!(value(&mut engine, "loop"), json!(10));
+    }
+
+    #[test]
+    fn for_each_collects_and_fold_accumulates() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "list", "rangeNode", vec![json!(1), json!(5), json!(1)]);
+        add(&mut engine, "each", FOR_EACH_NODE);
+        add(&mut engine, "fold", FOLD_NODE);
+        connect(&mut engine, "list", 0, "each", 0);
+        connect(&mut engine, "list", 0, "fold", 0);
+        sum_body(&mut engine, "each", &["in-1", "in-2"], json!(0));
+        sum_body(&mut engine, "fold", &["in-1", "in-2"], json!(0));
+
+        // item + index
+        assert_eq!(value(&mut engine, "each"), json!([1, 3, 5, 7]));
+        // 0 + 1 + 2 + 3 + 4
+        assert_eq!(value(&mut engine, "fold"), json!(10));
+    }
+
+    #[test]
+    fn loops_stop_at_the_iteration_limit() {
+        let mut engine = GraphEngine::new();
+        engine.set_iteration_limit(100);
+        add_with(&mut engine, "loop", REPEAT_NODE, vec![json!(101), json!(0)]);
+        assert_eq!(error(&mut engine, "loop").code, "ITERATION_LIMIT");
+
+        engine.update_node_data("loop", vec![json!(-1), json!(0)]).unwrap();
+        assert_eq!(error(&mut engine, "loop").code, "LOGIC_FAILED");
+        engine.update_node_data("loop", vec![json!(2.5), json!(0)]).unwrap();
+        assert!(error(&mut engine, "loop").message.contains("whole number"));
+    }
+
+    #[test]
+    fn body_inputs_follow_their_names_after_edits() {
+        let mut engine = GraphEngine::new();
+        add_with(&mut engine, "list", "rangeNode", vec![json!(1), json!(5), json!(1)]);
+        add(&mut engine, "fold", FOLD_NODE);
+        connect(&mut engine, "list", 0, "fold", 0);
+        engine.enter_group("fold").unwrap();
+        // Replace the accumulator input: the new one ends up last in `inputs`
+        engine.remove_node("in-1").unwrap();
+        add_with(&mut engine, "acc", "groupInputNode", vec![json!("accumulator")]);
+        add_with(&mut engine, "sum", "additionNode", vec![json!(0), json!(0)]);
+        connect(&mut engine, "acc", 0, "sum", 0);
+        connect(&mut engine, "in-2", 0, "sum", 1);
+        connect(&mut engine, "sum", 0, "out-1", 0);
+        engine.exit_group().unwrap();
+        assert_eq!(engine.node("fold").unwrap().group.as_ref().unwrap().inputs, ["in-2", "in-3", "acc"]);
+
+        // 0 + 1 + 2 + 3 + 4
+        assert_eq!(value(&mut engine, "fold"), json!(10));
+    }
+
+    #[test]
+    fn list_loops_only_take_lists() {
+        let mut engine = GraphEngine::new();
+        add(&mut engine, "n", "numberNode");
+        add(&mut engine, "each", FOR_EACH_NODE);
+        add(&mut engine, "fold", FOLD_NODE);
+        for target in ["each", "fold"] {
+            let err = engine.can_connect("n".into(), 0, target.into(), 0).unwrap_err();
+            assert_eq!(err.code(), "TYPE_MISMATCH");
+        }
+    }
+
+    #[test]
+    fn body_failures_name_the_iteration() {
+        let mut engine = GraphEngine::new();
+        engine.set_numeric_policy(crate::NumericPolicy::Error);
+        add_with(&mut engine, "loop", REPEAT_NODE, vec![json!(3), json!(1)]);
+        engine.enter_group("loop").unwrap();
+        add_with(&mut engine, "div", "divisionNode", vec![json!(1), json!(0)]);
+        connect(&mut engine, "in-2", 0, "div", 1);
+        engine.remove_edge("in-1".into(), 0, "out-1".into(), 0).unwrap();
+        connect(&mut engine, "div", 0, "out-1", 0);
+        engine.exit_group().unwrap();
+
+        let failure = error(&mut engine, "loop");
+        assert_eq!(failure.origin, "loop");
+        assert!(failure.message.contains("iteration 0"), "{}", failure.message);
+    }
+}
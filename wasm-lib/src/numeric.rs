@@ -0,0 +1,557 @@
+use serde_json::Value as SerdeValue;
+use std::cmp::Ordering;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+// --- Numbers ---
+
+// Math nodes keep integers and floats apart: integer inputs give integer
+// results where the operation allows it (3 + 4 = 7, 6 / 3 = 2), anything else
+// (7 / 2, a float input, an integer overflow) falls back to a float. Results
+// that are not finite numbers go through the engine's `NumericPolicy`.
+//
+// With the `decimal` feature and decimal mode on (`set_decimal_mode`), number
+// inputs are read as exact decimals instead (0.1 + 0.2 = 0.3), and decimal
+// results are sent as `{ "decimal": "0.3", "display": 0.3 }`: the exact value
+// as a string plus the nearest float for display. Operations without a
+// decimal form (sqrt, sin, ...) still run on floats.
+
+/// What a math node does when its result is not a finite number
+/// (division by zero, square root of a negative number, ...).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NumericPolicy {
+    /// Fail the node with an error naming the operation.
+    Error,
+    /// Output NaN / Infinity (as `{ "number": "NaN" }`, see `number_value`).
+    #[default]
+    NonFinite,
+    /// Output 0 instead.
+    Zero,
+}
+
+impl NumericPolicy {
+    /// Converts a result to a value, applying the policy if it is not finite.
+    /// `operation` names what produced it, for the error message.
+    pub fn finish(self, result: Num, operation: &str) -> Result<SerdeValue, String> {
+        match result {
+            Num::Float(value) if !value.is_finite() => match self {
+                NumericPolicy::Error => Err(format!("{} gives {}", operation, non_finite_name(value))),
+                NumericPolicy::NonFinite => Ok(number_value(value)),
+                NumericPolicy::Zero => Ok(serde_json::json!(0)),
+            },
+            finite => Ok(finite.to_value()),
+        }
+    }
+}
+
+/// Engine-wide number settings handed to node logic through `LogicInputs::numeric`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NumericSettings {
+    pub policy: NumericPolicy,
+    // Read every number input as an exact decimal
+    #[cfg(feature = "decimal")]
+    pub decimal: bool,
+}
+
+impl NumericSettings {
+    /// Reads a number input, as a decimal in decimal mode.
+    pub fn read(self, value: &SerdeValue) -> Option<Num> {
+        let num = Num::from_value(value)?;
+        #[cfg(feature = "decimal")]
+        if self.decimal {
+            return Some(num.to_decimal().map_or(num, Num::Decimal));
+        }
+        Some(num)
+    }
+
+    pub fn finish(self, result: Num, operation: &str) -> Result<SerdeValue, String> {
+        self.policy.finish(result, operation)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Num {
+    Int(i64),
+    Float(f64),
+    #[cfg(feature = "decimal")]
+    Decimal(Decimal),
+}
+
+/// One arithmetic operation in each representation. `None` from the integer
+/// (or decimal) form means it has no exact result there (overflow, an inexact
+/// division, division by zero) and the float form is used instead.
+pub struct BinaryOp {
+    pub int: fn(i64, i64) -> Option<i64>,
+    pub float: fn(f64, f64) -> f64,
+    #[cfg(feature = "decimal")]
+    pub decimal: fn(Decimal, Decimal) -> Option<Decimal>,
+}
+
+pub const ADD: BinaryOp = BinaryOp {
+    int: i64::checked_add,
+    float: |a, b| a + b,
+    #[cfg(feature = "decimal")]
+    decimal: Decimal::checked_add,
+};
+
+pub const SUBTRACT: BinaryOp = BinaryOp {
+    int: i64::checked_sub,
+    float: |a, b| a - b,
+    #[cfg(feature = "decimal")]
+    decimal: Decimal::checked_sub,
+};
+
+pub const MULTIPLY: BinaryOp = BinaryOp {
+    int: i64::checked_mul,
+    float: |a, b| a * b,
+    #[cfg(feature = "decimal")]
+    decimal: Decimal::checked_mul,
+};
+
+// Stays an integer only when the division is exact
+pub const DIVIDE: BinaryOp = BinaryOp {
+    int: |a, b| if b != 0 && a % b == 0 { a.checked_div(b) } else { None },
+    float: |a, b| a / b,
+    #[cfg(feature = "decimal")]
+    decimal: Decimal::checked_div,
+};
+
+// Sign follows the dividend, like JS `%`
+pub const REMAINDER: BinaryOp = BinaryOp {
+    int: i64::checked_rem,
+    float: |a, b| a % b,
+    #[cfg(feature = "decimal")]
+    decimal: Decimal::checked_rem,
+};
+
+// Exact only for whole, non-negative exponents
+pub const POWER: BinaryOp = BinaryOp {
+    int: |a, b| u32::try_from(b).ok().and_then(|b| a.checked_pow(b)),
+    float: f64::powf,
+    #[cfg(feature = "decimal")]
+    decimal: |a, b| {
+        let exponent = b.is_integer().then(|| b.to_u32()).flatten()?;
+        (0..exponent).try_fold(Decimal::ONE, |acc, _| acc.checked_mul(a))
+    },
+};
+
+pub const MIN: BinaryOp = BinaryOp {
+    int: |a, b| Some(a.min(b)),
+    float: f64::min,
+    #[cfg(feature = "decimal")]
+    decimal: |a, b| Some(a.min(b)),
+};
+
+pub const MAX: BinaryOp = BinaryOp {
+    int: |a, b| Some(a.max(b)),
+    float: f64::max,
+    #[cfg(feature = "decimal")]
+    decimal: |a, b| Some(a.max(b)),
+};
+
+/// Direction for `Num::round`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+    // Halves away from zero
+    Nearest,
+}
+
+impl Num {
+    /// Reads a number value (including tagged NaN / Infinity and decimal
+    /// objects); integers too large for i64 become floats.
+    pub fn from_value(value: &SerdeValue) -> Option<Num> {
+        match value {
+            SerdeValue::Number(n) => Some(n.as_i64().map_or_else(|| Num::Float(n.as_f64().unwrap_or(f64::NAN)), Num::Int)),
+            #[cfg(feature = "decimal")]
+            SerdeValue::Object(_) if decimal_text(value).is_some() => {
+                decimal_text(value)?.parse().ok().map(Num::Decimal).or_else(|| as_number(value).map(Num::Float))
+            }
+            other => as_number(other).map(Num::Float),
+        }
+    }
+
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Num::Int(n) => n as f64,
+            Num::Float(f) => f,
+            #[cfg(feature = "decimal")]
+            Num::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// The value as an i64 if it is a whole number that fits one.
+    pub fn as_whole(self) -> Option<i64> {
+        match self {
+            Num::Int(n) => Some(n),
+            // i64::MAX as f64 rounds up to 2^63, which is already out of range
+            Num::Float(f) => (f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64).then_some(f as i64),
+            #[cfg(feature = "decimal")]
+            Num::Decimal(d) => d.fract().is_zero().then(|| d.to_i64()).flatten(),
+        }
+    }
+
+    pub fn to_value(self) -> SerdeValue {
+        match self {
+            Num::Int(n) => serde_json::json!(n),
+            Num::Float(f) => number_value(f),
+            #[cfg(feature = "decimal")]
+            Num::Decimal(d) => decimal_value(d),
+        }
+    }
+
+    /// The exact decimal for this number; floats are read by their shortest
+    /// decimal form (0.1 is 0.1, not 0.1000000000000000055...).
+    #[cfg(feature = "decimal")]
+    pub fn to_decimal(self) -> Option<Decimal> {
+        match self {
+            Num::Int(n) => Some(Decimal::from(n)),
+            Num::Float(f) if f.is_finite() => f.to_string().parse().ok().or_else(|| Decimal::from_f64(f)),
+            Num::Float(_) => None,
+            Num::Decimal(d) => Some(d),
+        }
+    }
+
+    /// Applies `op` in the most exact representation both sides share:
+    /// decimal if either is one, integer if both are, float otherwise (or
+    /// when the exact form has no result).
+    pub fn combine(self, other: Num, op: &BinaryOp) -> Num {
+        let float = |a: Num, b: Num| Num::Float((op.float)(a.as_f64(), b.as_f64()));
+        match (self, other) {
+            (Num::Int(a), Num::Int(b)) => (op.int)(a, b).map_or_else(|| float(self, other), Num::Int),
+            #[cfg(feature = "decimal")]
+            (Num::Decimal(_), _) | (_, Num::Decimal(_)) => match (self.to_decimal(), other.to_decimal()) {
+                (Some(a), Some(b)) => (op.decimal)(a, b).map_or_else(|| float(self, other), Num::Decimal),
+                _ => float(self, other),
+            },
+            (a, b) => float(a, b),
+        }
+    }
+
+    /// Rounds to `precision` decimal places (negative rounds to tens,
+    /// hundreds...). With no decimal places left the result is an integer
+    /// if it fits.
+    pub fn round(self, precision: i32, rounding: Rounding) -> Num {
+        match self {
+            Num::Int(_) if precision >= 0 => self,
+            #[cfg(feature = "decimal")]
+            Num::Decimal(d) => round_decimal(d, precision, rounding).map_or_else(|| Num::Float(round_float(d.to_f64().unwrap_or(f64::NAN), precision, rounding)), Num::Decimal),
+            _ => {
+                let rounded = round_float(self.as_f64(), precision, rounding);
+                if precision <= 0 && rounded.abs() < MAX_SAFE_INTEGER { Num::Int(rounded as i64) } else { Num::Float(rounded) }
+            }
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.as_f64() == 0.0
+    }
+}
+
+pub fn add(a: Num, b: Num) -> Num {
+    a.combine(b, &ADD)
+}
+
+pub fn subtract(a: Num, b: Num) -> Num {
+    a.combine(b, &SUBTRACT)
+}
+
+pub fn multiply(a: Num, b: Num) -> Num {
+    a.combine(b, &MULTIPLY)
+}
+
+pub fn divide(a: Num, b: Num) -> Num {
+    a.combine(b, &DIVIDE)
+}
+
+/// Euclidean division (`divModNode`): the remainder `r` is never negative
+/// (0 <= r < |b|) and `q * b + r == a` for either sign of `b`.
+pub fn div_mod(a: Num, b: Num) -> (Num, Num) {
+    match (a, b) {
+        (Num::Int(a), Num::Int(b)) if b != 0 => match (a.checked_div_euclid(b), a.checked_rem_euclid(b)) {
+            (Some(q), Some(r)) => (Num::Int(q), Num::Int(r)),
+            _ => div_mod_float(a as f64, b as f64),
+        },
+        #[cfg(feature = "decimal")]
+        (Num::Decimal(_), _) | (_, Num::Decimal(_)) => {
+            let exact = a.to_decimal().zip(b.to_decimal()).and_then(|(a, b)| {
+                let remainder = a.checked_rem(b)?;
+                let remainder = if remainder.is_sign_negative() && !remainder.is_zero() { remainder + b.abs() } else { remainder };
+                // a - r is a multiple of b, so this divides exactly
+                let quotient = (a - remainder).checked_div(b)?;
+                Some((Num::Decimal(quotient), Num::Decimal(remainder)))
+            });
+            exact.unwrap_or_else(|| div_mod_float(a.as_f64(), b.as_f64()))
+        }
+        (a, b) => div_mod_float(a.as_f64(), b.as_f64()),
+    }
+}
+
+fn div_mod_float(a: f64, b: f64) -> (Num, Num) {
+    (Num::Float(a.div_euclid(b)), Num::Float(a.rem_euclid(b)))
+}
+
+/// Orders two numbers, treating them as equal within `tolerance`; None if
+/// either is NaN. Decimals compare exactly.
+pub fn compare(a: Num, b: Num, tolerance: f64) -> Option<Ordering> {
+    #[cfg(feature = "decimal")]
+    if let (Num::Decimal(_), _) | (_, Num::Decimal(_)) = (a, b) {
+        if let (Some(x), Some(y), Some(tolerance)) = (a.to_decimal(), b.to_decimal(), Decimal::from_f64(tolerance)) {
+            return Some(if (x - y).abs() <= tolerance { Ordering::Equal } else { x.cmp(&y) });
+        }
+    }
+    let (x, y) = (a.as_f64(), b.as_f64());
+    if x.is_nan() || y.is_nan() {
+        return None;
+    }
+    Some(if (x - y).abs() <= tolerance || x == y { Ordering::Equal } else { x.total_cmp(&y) })
+}
+
+// Largest float that still holds every integer exactly (2^53)
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+fn round_float(value: f64, precision: i32, rounding: Rounding) -> f64 {
+    let factor = 10f64.powi(precision);
+    let scaled = value * factor;
+    if !scaled.is_finite() {
+        return value;
+    }
+    let rounded = match rounding {
+        Rounding::Floor => scaled.floor(),
+        Rounding::Ceil => scaled.ceil(),
+        Rounding::Nearest => scaled.round(),
+    };
+    rounded / factor
+}
+
+#[cfg(feature = "decimal")]
+fn round_decimal(value: Decimal, precision: i32, rounding: Rounding) -> Option<Decimal> {
+    use rust_decimal::RoundingStrategy;
+    let strategy = match rounding {
+        Rounding::Floor => RoundingStrategy::ToNegativeInfinity,
+        Rounding::Ceil => RoundingStrategy::ToPositiveInfinity,
+        Rounding::Nearest => RoundingStrategy::MidpointAwayFromZero,
+    };
+    if precision >= 0 {
+        return Some(value.round_dp_with_strategy(precision as u32, strategy));
+    }
+    // Round to tens, hundreds...: scale down, round to a whole number, scale back up
+    let factor = Decimal::from_i128_with_scale(10i128.checked_pow(precision.unsigned_abs())?, 0);
+    value.checked_div(factor)?.round_dp_with_strategy(0, strategy).checked_mul(factor)
+}
+
+#[cfg(feature = "decimal")]
+pub fn decimal_value(value: Decimal) -> SerdeValue {
+    serde_json::json!({ "decimal": value.to_string(), "display": value.to_f64() })
+}
+
+// The exact digits of a `{ "decimal": "..." }` value
+fn decimal_text(value: &SerdeValue) -> Option<&str> {
+    value.as_object()?.get("decimal")?.as_str()
+}
+
+// JSON has no NaN or Infinity, so they are emitted tagged, as
+// `{ "number": "NaN" }`, `"Infinity"` or `"-Infinity"` (what JS `String()`
+// gives), instead of letting serde_json turn them into null. `as_number` reads
+// them back, so they flow on through number sockets; plain strings that happen
+// to spell "NaN" stay strings.
+pub fn number_value(value: f64) -> SerdeValue {
+    if value.is_finite() {
+        serde_json::json!(value)
+    } else {
+        serde_json::json!({ "number": non_finite_name(value) })
+    }
+}
+
+fn non_finite_name(value: f64) -> &'static str {
+    if value.is_nan() {
+        "NaN"
+    } else if value > 0.0 {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+/// Text of a number carried as an object: the exact digits of a decimal, or
+/// "NaN" / "Infinity" / "-Infinity".
+pub fn number_text(value: &SerdeValue) -> Option<&str> {
+    non_finite_text(value).or_else(|| decimal_text(value))
+}
+
+// The name inside a tagged NaN / Infinity value
+fn non_finite_text(value: &SerdeValue) -> Option<&str> {
+    let object = value.as_object().filter(|object| object.len() == 1)?;
+    object.get("number")?.as_str().filter(|name| ["NaN", "Infinity", "-Infinity"].contains(name))
+}
+
+// Any value a number socket accepts, as a float. Decimal values are read even
+// without the `decimal` feature (approximately).
+pub fn as_number(value: &SerdeValue) -> Option<f64> {
+    match value {
+        SerdeValue::Number(n) => n.as_f64(),
+        SerdeValue::Object(_) => match non_finite_text(value) {
+            Some("NaN") => Some(f64::NAN),
+            Some("Infinity") => Some(f64::INFINITY),
+            Some(_) => Some(f64::NEG_INFINITY),
+            None => decimal_text(value)?.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::GraphEngine;
+    use crate::test_util::{self, run, run_on};
+    use serde_json::json;
+
+    fn int(value: i64) -> Num {
+        Num::Int(value)
+    }
+
+    #[test]
+    fn integers_stay_integers_when_exact() {
+        assert_eq!(add(int(3), int(4)), int(7));
+        assert_eq!(divide(int(6), int(3)), int(2));
+        assert_eq!(divide(int(7), int(2)), Num::Float(3.5));
+        assert_eq!(multiply(int(i64::MAX), int(2)), Num::Float(i64::MAX as f64 * 2.0));
+        assert_eq!(add(int(1), Num::Float(0.5)), Num::Float(1.5));
+        assert_eq!(Num::from_value(&json!(2.0)), Some(Num::Float(2.0)));
+        assert_eq!(div_mod(int(-7), int(2)), (int(-4), int(1)));
+    }
+
+    #[test]
+    fn div_mod_is_euclidean_for_every_sign() {
+        for (a, b) in [(7, 2), (-7, 2), (7, -2), (-7, -2), (6, -3)] {
+            let (q, r) = div_mod(int(a), int(b));
+            let (Num::Int(q), Num::Int(r)) = (q, r) else { panic!("{} divMod {} left the integers", a, b) };
+            assert!((0..b.abs()).contains(&r), "{} divMod {} gives remainder {}", a, b, r);
+            assert_eq!(q * b + r, a);
+        }
+        assert_eq!(div_mod(int(7), int(-2)), (int(-3), int(1)));
+        assert_eq!(div_mod(Num::Float(7.5), Num::Float(-2.0)), (Num::Float(-3.0), Num::Float(1.5)));
+        assert_eq!(div_mod(Num::Float(-7.5), Num::Float(-2.0)), (Num::Float(4.0), Num::Float(0.5)));
+        let (q, r) = div_mod(int(i64::MIN), int(-1));
+        assert_eq!((q.as_f64(), r.as_f64()), (9.223372036854776e18, 0.0));
+    }
+
+    #[test]
+    fn compare_uses_the_tolerance() {
+        assert_eq!(compare(int(1), Num::Float(1.0), 0.0), Some(Ordering::Equal));
+        assert_eq!(compare(Num::Float(0.1 + 0.2), Num::Float(0.3), 1e-9), Some(Ordering::Equal));
+        assert_eq!(compare(int(1), int(2), 0.0), Some(Ordering::Less));
+        assert_eq!(compare(Num::Float(f64::NAN), int(0), 0.0), None);
+    }
+
+    #[test]
+    fn policy_decides_non_finite_results() {
+        let divide_by_zero = |engine: &mut GraphEngine| run_on(engine, "divisionNode", vec![json!(1), json!(0)]);
+        let mut engine = GraphEngine::new();
+        assert_eq!(divide_by_zero(&mut engine).unwrap(), [number_value(f64::INFINITY)]);
+
+        engine.set_numeric_policy(NumericPolicy::Zero);
+        assert_eq!(divide_by_zero(&mut engine).unwrap(), [json!(0)]);
+
+        engine.set_numeric_policy(NumericPolicy::Error);
+        let failure = divide_by_zero(&mut engine).unwrap_err();
+        assert!(failure.message.starts_with("Division by zero"), "{}", failure.message);
+        assert!(run_on(&mut engine, "sqrtNode", vec![json!(-1)]).is_err());
+    }
+
+    #[test]
+    fn non_finite_values_round_trip_as_numbers() {
+        let infinity = number_value(f64::INFINITY);
+        assert_eq!(infinity, json!({ "number": "Infinity" }));
+        assert_eq!(as_number(&infinity), Some(f64::INFINITY));
+        assert_eq!(as_number(&number_value(f64::NEG_INFINITY)), Some(f64::NEG_INFINITY));
+        assert!(as_number(&number_value(f64::NAN)).unwrap().is_nan());
+        assert_eq!(number_value(1.5), json!(1.5));
+        assert_eq!(as_number(&json!("Infinity")), None);
+    }
+
+    #[test]
+    fn strings_spelling_non_finite_numbers_stay_strings() {
+        let compare = |a: SerdeValue, op: &str, b: SerdeValue| run("comparisonNode", vec![a, b, json!(op), json!(0)]);
+        assert_eq!(compare(json!("NaN"), "==", json!("NaN")).unwrap(), [json!(true)]);
+        assert!(compare(json!("Infinity"), "<", json!(1)).is_err());
+        assert_eq!(compare(number_value(f64::NAN), "==", number_value(f64::NAN)).unwrap(), [json!(false)]);
+        assert_eq!(compare(number_value(f64::INFINITY), ">", json!(1)).unwrap(), [json!(true)]);
+        assert_eq!(run("additionNode", vec![json!("Infinity"), json!(1)]).unwrap(), [json!("Infinity1")]);
+        assert_eq!(run("additionNode", vec![number_value(f64::INFINITY), json!("!")]).unwrap(), [json!("Infinity!")]);
+    }
+
+    #[test]
+    fn non_numbers_on_number_sockets_fail_the_node() {
+        let mut engine = GraphEngine::new();
+        test_util::add_with(&mut engine, "text", "stringNode", vec![json!("abc")]);
+        test_util::add(&mut engine, "sum", "additionNode");
+        test_util::add(&mut engine, "sub", "subtractionNode");
+        test_util::connect(&mut engine, "text", 0, "sum", 0);
+        test_util::connect(&mut engine, "sum", 0, "sub", 0);
+        let failure = test_util::error(&mut engine, "sub");
+        assert_eq!(failure.code, "INVALID_INPUT_VALUE");
+        assert_eq!(failure.origin, "sub");
+        assert!(run("subtractionNode", vec![json!("1"), json!(0)]).is_err());
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimal_mode_is_exact() {
+        let mut engine = GraphEngine::new();
+        engine.set_decimal_mode(true);
+        let sum = run_on(&mut engine, "additionNode", vec![json!(0.1), json!(0.2)]).unwrap();
+        assert_eq!(sum, [json!({ "decimal": "0.3", "display": 0.3 })]);
+        let third = run_on(&mut engine, "divisionNode", vec![json!(1), json!(3)]).unwrap().remove(0);
+        let back = run_on(&mut engine, "multiplicationNode", vec![third, json!(3)]).unwrap().remove(0);
+        assert_eq!(as_number(&back), Some(1.0));
+        let rounded = run_on(&mut engine, "roundNode", vec![json!(2.675), json!(2)]).unwrap();
+        assert_eq!(rounded, [json!({ "decimal": "2.68", "display": 2.68 })]);
+        let equal = run_on(&mut engine, "comparisonNode", vec![sum[0].clone(), json!(0.3), json!("=="), json!(0)]).unwrap();
+        assert_eq!(equal, [json!(true)]);
+        let div_mod = run_on(&mut engine, "divModNode", vec![json!(-7.5), json!(-2)]).unwrap();
+        assert_eq!(div_mod.iter().map(|v| as_number(v).unwrap()).collect::<Vec<_>>(), [4.0, 0.5]);
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimals_read_as_their_digits_in_strings() {
+        let mut engine = GraphEngine::new();
+        engine.set_decimal_mode(true);
+        test_util::add_with(&mut engine, "sum", "additionNode", vec![json!(0.1), json!(0.2)]);
+        test_util::add_with(&mut engine, "twice", "repeatStringNode", vec![json!(""), json!(2)]);
+        test_util::add_with(&mut engine, "label", "additionNode", vec![json!(0), json!(" total")]);
+        test_util::connect(&mut engine, "sum", 0, "twice", 0);
+        test_util::connect(&mut engine, "sum", 0, "label", 0);
+        assert_eq!(test_util::value(&mut engine, "twice"), json!("0.30.3"));
+        assert_eq!(test_util::value(&mut engine, "label"), json!("0.3 total"));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimals_work_as_indices_and_counts() {
+        let mut engine = GraphEngine::new();
+        engine.set_decimal_mode(true);
+        test_util::add_with(&mut engine, "one", "additionNode", vec![json!(0.5), json!(0.5)]);
+        test_util::add_with(&mut engine, "get", "listGetNode", vec![json!(["a", "b"]), json!(0)]);
+        test_util::add_with(&mut engine, "range", "rangeNode", vec![json!(0), json!(3), json!(1)]);
+        test_util::add_with(&mut engine, "repeat", "repeatStringNode", vec![json!("ab"), json!(0)]);
+        test_util::connect(&mut engine, "one", 0, "get", 1);
+        test_util::connect(&mut engine, "one", 0, "range", 0);
+        test_util::connect(&mut engine, "one", 0, "repeat", 1);
+        assert_eq!(test_util::value(&mut engine, "get"), json!("b"));
+        assert_eq!(test_util::value(&mut engine, "range"), json!([1, 2]));
+        assert_eq!(test_util::value(&mut engine, "repeat"), json!("ab"));
+
+        engine.update_node_data("one", vec![json!(0.5), json!(0.2)]).unwrap();
+        assert!(test_util::error(&mut engine, "get").message.contains("whole number, got 0.7"));
+    }
+}
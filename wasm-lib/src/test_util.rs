@@ -0,0 +1,59 @@
+// Helpers shared by the unit tests: build graphs on a `GraphEngine` and read
+// evaluation results back.
+use crate::engine::GraphEngine;
+use crate::evaluator::NodeError;
+use crate::graph::ConnectionPolicy;
+use serde_json::Value as SerdeValue;
+
+/// Adds a node with its type's default data.
+pub fn add(engine: &mut GraphEngine, id: &str, node_type: &str) {
+    let data = engine.get_node_defs()[node_type].data.iter().map(|def| def.default_value.clone()).collect();
+    add_with(engine, id, node_type, data);
+}
+
+pub fn add_with(engine: &mut GraphEngine, id: &str, node_type: &str, data: Vec<SerdeValue>) {
+    engine.add_node(id.to_string(), node_type.to_string(), data).unwrap();
+}
+
+pub fn connect(engine: &mut GraphEngine, source: &str, output: usize, target: &str, input: usize) {
+    engine.add_edge(source.to_string(), output, target.to_string(), input, ConnectionPolicy::Reject).unwrap();
+}
+
+/// Output values of `id` after evaluating it; panics if the node failed.
+pub fn outputs(engine: &mut GraphEngine, id: &str) -> Vec<SerdeValue> {
+    let mut trace = engine.evaluate_node(id).unwrap();
+    let cache = trace.remove(id).unwrap();
+    if let Some(error) = cache.error {
+        panic!("node `{}` failed: {} ({})", id, error.message, error.code);
+    }
+    cache.outputs
+}
+
+/// First output value of `id`.
+pub fn value(engine: &mut GraphEngine, id: &str) -> SerdeValue {
+    outputs(engine, id).remove(0)
+}
+
+/// Why `id` failed; panics if it evaluated.
+pub fn error(engine: &mut GraphEngine, id: &str) -> NodeError {
+    let mut trace = engine.evaluate_node(id).unwrap();
+    match trace.remove(id).unwrap().error {
+        Some(error) => error,
+        None => panic!("node `{}` did not fail", id),
+    }
+}
+
+/// Evaluates a single node of `node_type` with `data` in its slots (no edges).
+pub fn run(node_type: &str, data: Vec<SerdeValue>) -> Result<Vec<SerdeValue>, NodeError> {
+    run_on(&mut GraphEngine::new(), node_type, data)
+}
+
+pub fn run_on(engine: &mut GraphEngine, node_type: &str, data: Vec<SerdeValue>) -> Result<Vec<SerdeValue>, NodeError> {
+    let id = format!("test-{}", engine.nodes().count());
+    add_with(engine, &id, node_type, data);
+    let cache = engine.evaluate_node(&id).unwrap().remove(&id).unwrap();
+    match cache.error {
+        Some(error) => Err(error),
+        None => Ok(cache.outputs),
+    }
+}
@@ -0,0 +1,296 @@
+use crate::definitions::{NodeDefinition, NodeSchema};
+use crate::graph::{Edge, InputConnection, Node, OutputConnection};
+use crate::group;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+// --- Graph Validation ---
+
+// Every edge is stored twice, on the source's `outputs` and on the target's
+// `inputs`, and a node's socket and data arrays are sized from its schema when
+// it is added. `validate` reports anything that no longer lines up (a graph
+// built only through the engine API has no issues); `repair` fixes what it can:
+// sockets and data are resized to the schema (new data slots get their
+// default), duplicate connections are merged and edges recorded on one side
+// only are dropped. Unknown node types are reported but left alone.
+// Group and loop bodies are checked too, with `path` naming the enclosing nodes.
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GraphIssue {
+    pub code: &'static str, // "UNKNOWN_NODE_TYPE", "SOCKET_COUNT", "DATA_LENGTH", "DANGLING_EDGE", "INVALID_SOCKET_INDEX", "HALF_EDGE", "DUPLICATE_EDGE"
+    pub message: String,
+    #[serde(rename = "nodeId")]
+    pub node_id: String,
+    // Enclosing group / loop nodes, outermost first (empty at the top level)
+    pub path: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edge: Option<Edge>,
+    // Whether `repair` fixes it
+    pub repairable: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<GraphIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+// What `repair` did: the issues it fixed, those still there, and the
+// (top-level) nodes whose cached values were invalidated.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RepairReport {
+    pub repaired: Vec<GraphIssue>,
+    pub remaining: Vec<GraphIssue>,
+    pub dirty: Vec<String>,
+}
+
+// (source, output index, target, input index)
+type EdgeKey = (String, usize, String, usize);
+
+/// Checks `nodes` (and the bodies of group / loop nodes in it) against the registry.
+pub fn validate(nodes: &HashMap<String, Node>, registry: &HashMap<String, NodeDefinition>) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    validate_graph(nodes, registry, &mut Vec::new(), &mut report.issues);
+    report
+}
+
+/// Fixes the repairable issues of `nodes` in place, innermost graphs first.
+pub fn repair(nodes: &mut HashMap<String, Node>, registry: &HashMap<String, NodeDefinition>) {
+    for node in nodes.values_mut() {
+        if let Some(body) = &mut node.group {
+            repair(&mut body.nodes, registry);
+        }
+        let Some(schema) = node_schema(node, registry) else { continue };
+
+        node.inputs.resize_with(schema.io.inputs.len(), Vec::new);
+        node.outputs.resize_with(schema.io.outputs.len(), Vec::new);
+        node.data.truncate(schema.data.len());
+        let present = node.data.len();
+        node.data.extend(schema.data[present..].iter().map(|def| def.default_value.clone()));
+
+        for socket in &mut node.inputs {
+            let mut seen = HashSet::new();
+            socket.retain(|c| seen.insert((c.id.clone(), c.output_index)));
+        }
+        for socket in &mut node.outputs {
+            let mut seen = HashSet::new();
+            socket.retain(|c| seen.insert((c.id.clone(), c.input_index)));
+        }
+    }
+
+    // Keep only the halves whose counterpart exists (this also drops edges to
+    // missing nodes or sockets, including those just cut off by resizing)
+    let (output_halves, input_halves) = edge_halves(nodes);
+    for node in nodes.values_mut() {
+        for (index, socket) in node.outputs.iter_mut().enumerate() {
+            socket.retain(|c| input_halves.contains(&output_key(&node.id, index, c)));
+        }
+        for (index, socket) in node.inputs.iter_mut().enumerate() {
+            socket.retain(|c| output_halves.contains(&input_key(&node.id, index, c)));
+        }
+    }
+}
+
+fn validate_graph(nodes: &HashMap<String, Node>, registry: &HashMap<String, NodeDefinition>, path: &mut Vec<String>, issues: &mut Vec<GraphIssue>) {
+    // Sorted, so the report is stable
+    let mut ids: Vec<&String> = nodes.keys().collect();
+    ids.sort();
+
+    for id in ids {
+        let node = &nodes[id];
+        let mut issue = |code, message: String, edge: Option<Edge>, repairable| issues.push(GraphIssue {
+            code,
+            message,
+            node_id: id.clone(),
+            path: path.clone(),
+            edge,
+            repairable,
+        });
+
+        match node_schema(node, registry) {
+            None => issue("UNKNOWN_NODE_TYPE", format!("Node `{}` has unknown type {}.", id, node.node_type), None, false),
+            Some(schema) => {
+                for (is_input, count, expected) in [(true, node.inputs.len(), schema.io.inputs.len()), (false, node.outputs.len(), schema.io.outputs.len())] {
+                    if count != expected {
+                        let direction = if is_input { "input" } else { "output" };
+                        issue("SOCKET_COUNT", format!("Node `{}` has {} {} sockets, its type declares {}.", id, count, direction, expected), None, true);
+                    }
+                }
+                if node.data.len() != schema.data.len() {
+                    issue("DATA_LENGTH", format!("Node `{}` has {} data values, its type declares {}.", id, node.data.len(), schema.data.len()), None, true);
+                }
+            }
+        }
+
+        for (index, socket) in node.outputs.iter().enumerate() {
+            let mut seen = HashSet::new();
+            for conn in socket {
+                let edge = Edge { source_id: id.clone(), source_output_index: index, target_id: conn.id.clone(), target_input_index: conn.input_index };
+                if !seen.insert((&conn.id, conn.input_index)) {
+                    issue("DUPLICATE_EDGE", format!("Output {} of node `{}` lists input {} of `{}` more than once.", index, id, conn.input_index, conn.id), Some(edge), true);
+                    continue;
+                }
+                match nodes.get(&conn.id) {
+                    None => issue("DANGLING_EDGE", format!("Output {} of node `{}` is connected to missing node `{}`.", index, id, conn.id), Some(edge), true),
+                    Some(target) => match target.inputs.get(conn.input_index) {
+                        None => issue("INVALID_SOCKET_INDEX", format!("Output {} of node `{}` is connected to input {} of `{}`, which has {}.", index, id, conn.input_index, conn.id, target.inputs.len()), Some(edge), true),
+                        Some(back) if !back.iter().any(|c| c.id == *id && c.output_index == index) => {
+                            issue("HALF_EDGE", format!("Edge from `{}` to `{}` is only recorded on the source.", id, conn.id), Some(edge), true)
+                        }
+                        Some(_) => {}
+                    },
+                }
+            }
+        }
+
+        for (index, socket) in node.inputs.iter().enumerate() {
+            let mut seen = HashSet::new();
+            for conn in socket {
+                let edge = Edge { source_id: conn.id.clone(), source_output_index: conn.output_index, target_id: id.clone(), target_input_index: index };
+                if !seen.insert((&conn.id, conn.output_index)) {
+                    issue("DUPLICATE_EDGE", format!("Input {} of node `{}` lists output {} of `{}` more than once.", index, id, conn.output_index, conn.id), Some(edge), true);
+                    continue;
+                }
+                match nodes.get(&conn.id) {
+                    None => issue("DANGLING_EDGE", format!("Input {} of node `{}` is connected to missing node `{}`.", index, id, conn.id), Some(edge), true),
+                    Some(source) => match source.outputs.get(conn.output_index) {
+                        None => issue("INVALID_SOCKET_INDEX", format!("Input {} of node `{}` is connected to output {} of `{}`, which has {}.", index, id, conn.output_index, conn.id, source.outputs.len()), Some(edge), true),
+                        Some(back) if !back.iter().any(|c| c.id == *id && c.input_index == index) => {
+                            issue("HALF_EDGE", format!("Edge from `{}` to `{}` is only recorded on the target.", conn.id, id), Some(edge), true)
+                        }
+                        Some(_) => {}
+                    },
+                }
+            }
+        }
+
+        if let Some(body) = &node.group {
+            path.push(id.clone());
+            validate_graph(&body.nodes, registry, path, issues);
+            path.pop();
+        }
+    }
+}
+
+// A group's sockets come from its interface, everything else from the registry
+fn node_schema(node: &Node, registry: &HashMap<String, NodeDefinition>) -> Option<NodeSchema> {
+    match group::as_group(node) {
+        Some(group) => Some(group.schema()),
+        None => registry.get(&node.node_type).map(|def| def.schema.clone()),
+    }
+}
+
+// Every edge as recorded on its source, and as recorded on its target
+fn edge_halves(nodes: &HashMap<String, Node>) -> (HashSet<EdgeKey>, HashSet<EdgeKey>) {
+    let mut output_halves = HashSet::new();
+    let mut input_halves = HashSet::new();
+    for node in nodes.values() {
+        for (index, socket) in node.outputs.iter().enumerate() {
+            output_halves.extend(socket.iter().map(|c| output_key(&node.id, index, c)));
+        }
+        for (index, socket) in node.inputs.iter().enumerate() {
+            input_halves.extend(socket.iter().map(|c| input_key(&node.id, index, c)));
+        }
+    }
+    (output_halves, input_halves)
+}
+
+fn output_key(source_id: &str, index: usize, conn: &OutputConnection) -> EdgeKey {
+    (source_id.to_string(), index, conn.id.clone(), conn.input_index)
+}
+
+fn input_key(target_id: &str, index: usize, conn: &InputConnection) -> EdgeKey {
+    (conn.id.clone(), conn.output_index, target_id.to_string(), index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::get_node_registry;
+    use crate::engine::GraphEngine;
+    use crate::test_util::{add, connect};
+    use serde_json::json;
+
+    fn node(id: &str, node_type: &str, inputs: usize, outputs: usize, data: Vec<serde_json::Value>) -> (String, Node) {
+        (id.to_string(), Node {
+            id: id.to_string(),
+            node_type: node_type.to_string(),
+            inputs: vec![Vec::new(); inputs],
+            outputs: vec![Vec::new(); outputs],
+            data,
+            ui: None,
+            group: None,
+        })
+    }
+
+    fn codes(report: &ValidationReport) -> Vec<&'static str> {
+        report.issues.iter().map(|issue| issue.code).collect()
+    }
+
+    #[test]
+    fn graphs_built_through_the_engine_are_valid() {
+        let mut engine = GraphEngine::new();
+        add(&mut engine, "a", "numberNode");
+        add(&mut engine, "sum", "additionNode");
+        add(&mut engine, "loop", "repeatNode");
+        connect(&mut engine, "a", 0, "sum", 0);
+        connect(&mut engine, "sum", 0, "loop", 1);
+        engine.group_nodes("g".into(), vec!["a".into(), "sum".into()]).unwrap();
+        assert!(engine.validate().is_valid());
+    }
+
+    #[test]
+    fn finds_and_repairs_inconsistent_nodes() {
+        let registry = get_node_registry();
+        let mut nodes: HashMap<String, Node> = [
+            node("n", "numberNode", 1, 1, vec![]),
+            node("sum", "additionNode", 2, 1, vec![json!(1), json!(2), json!(3)]),
+            node("out", "outputNode", 1, 2, vec![json!(" ")]),
+            node("x", "mysteryNode", 0, 0, vec![]),
+        ].into_iter().collect();
+        let output = |id: &str, input_index| OutputConnection { id: id.to_string(), input_index };
+        let input = |id: &str, output_index| InputConnection { id: id.to_string(), output_index };
+        // n -> sum.0 twice on the source, once on the target; n -> out only on the source
+        nodes.get_mut("n").unwrap().outputs[0] = vec![output("sum", 0), output("sum", 0), output("sum", 7), output("ghost", 0), output("out", 0)];
+        nodes.get_mut("sum").unwrap().inputs[0] = vec![input("n", 0)];
+        nodes.get_mut("sum").unwrap().inputs[1] = vec![input("n", 4)];
+
+        let report = validate(&nodes, &registry);
+        assert_eq!(codes(&report), [
+            "DATA_LENGTH", "DUPLICATE_EDGE", "INVALID_SOCKET_INDEX", "DANGLING_EDGE", "HALF_EDGE",
+            "SOCKET_COUNT",
+            "DATA_LENGTH", "INVALID_SOCKET_INDEX",
+            "UNKNOWN_NODE_TYPE",
+        ]);
+        assert_eq!(report.issues[4].edge.as_ref().unwrap().target_id, "out");
+        assert!(!report.issues.last().unwrap().repairable);
+
+        repair(&mut nodes, &registry);
+        assert_eq!(codes(&validate(&nodes, &registry)), ["UNKNOWN_NODE_TYPE"]);
+        assert_eq!(nodes["n"].data, [json!(0)]);
+        assert_eq!(nodes["sum"].data, [json!(1), json!(2)]);
+        assert_eq!(nodes["out"].outputs.len(), 0);
+        assert_eq!(nodes["n"].outputs[0].len(), 1);
+        assert!(nodes["sum"].inputs[1].is_empty());
+    }
+
+    #[test]
+    fn engine_repair_reports_what_changed() {
+        let doc = r#"{"version":2,"nodes":[{"id":"q","type":"additionNode","data":[1]}],"edges":[]}"#;
+        let mut engine = GraphEngine::new();
+        engine.import_graph(crate::GraphDocument::from_json(doc).unwrap()).unwrap();
+        assert_eq!(codes(&engine.validate()), ["DATA_LENGTH"]);
+
+        let report = engine.repair();
+        assert_eq!(report.repaired.len(), 1);
+        assert!(report.remaining.is_empty());
+        assert_eq!(report.dirty, ["q"]);
+        assert_eq!(engine.node("q").unwrap().data, [json!(1), json!(0)]);
+        assert!(engine.repair().repaired.is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use crate::definitions;
+use crate::document::GraphDocument;
+use crate::engine;
+use crate::error::{EngineError, SocketRef};
+use crate::graph::ConnectionPolicy;
+use crate::numeric::NumericPolicy;
+use crate::history::GraphOp;
+use serde::Serialize;
+use serde_json::Value as SerdeValue;
+use wasm_bindgen::prelude::*;
+
+// --- JS Bindings ---
+
+// Thin wrapper around the native engine: converts arguments and results
+// between JsValue and Rust types, nothing else.
+#[wasm_bindgen]
+pub struct GraphEngine {
+    inner: engine::GraphEngine,
+}
+
+#[wasm_bindgen]
+impl GraphEngine {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> GraphEngine {
+        GraphEngine { inner: engine::GraphEngine::new() }
+    }
+
+    pub fn get_node_defs(&self) -> Result<JsValue, EngineError> {
+        Ok(serde_wasm_bindgen::to_value(&self.inner.get_node_defs())?)
+    }
+
+    /// Registers a graph (JSON document from `export_graph`) as a new node type,
+    /// returning its schema.
+    pub fn register_graph_node_type(&mut self, name: String, json: &str) -> Result<JsValue, EngineError> {
+        let schema = self.inner.register_graph_node_type(name, GraphDocument::from_json(json)?)?;
+        Ok(serde_wasm_bindgen::to_value(&schema)?)
+    }
+
+    pub fn set_iteration_limit(&mut self, limit: usize) {
+        self.inner.set_iteration_limit(limit);
+    }
+
+    pub fn set_numeric_policy(&mut self, policy: NumericPolicy) {
+        self.inner.set_numeric_policy(policy);
+    }
+
+    #[cfg(feature = "decimal")]
+    pub fn set_decimal_mode(&mut self, enabled: bool) {
+        self.inner.set_decimal_mode(enabled);
+    }
+
+    pub fn add_node(&mut self, id: String, node_type: String, data: JsValue) -> Result<(), EngineError> {
+        let parsed_data: Vec<SerdeValue> = serde_wasm_bindgen::from_value(data)?;
+        self.inner.add_node(id, node_type, parsed_data)
+    }
+
+    /// Type key a node added as `node_type` is stored under (deprecated keys map to their replacement).
+    pub fn resolve_node_type(&self, node_type: &str) -> String {
+        definitions::resolve_node_type(node_type).to_string()
+    }
+
+    pub fn set_node_ui(&mut self, id: &str, ui: JsValue) -> Result<(), EngineError> {
+        let parsed_ui: Option<SerdeValue> = serde_wasm_bindgen::from_value(ui)?;
+        self.inner.set_node_ui(id, parsed_ui)
+    }
+
+    /// The whole graph as a versioned JSON document string.
+    pub fn export_graph(&self) -> String {
+        self.inner.export_graph().to_json()
+    }
+
+    /// Replaces the whole graph with a JSON document from `export_graph`,
+    /// returning the report of nodes upgraded from older schema versions.
+    pub fn import_graph(&mut self, json: &str) -> Result<JsValue, EngineError> {
+        let report = self.inner.import_graph(GraphDocument::from_json(json)?)?;
+        Ok(serde_wasm_bindgen::to_value(&report)?)
+    }
+
+    pub fn remove_node(&mut self, id: &str) -> Result<Vec<String>, EngineError> {
+        self.inner.remove_node(id)
+    }
+
+    pub fn add_edge(&mut self, source_id: String, source_output_index: usize, target_id: String, target_input_index: usize, policy: ConnectionPolicy) -> Result<JsValue, EngineError> {
+        let update = self.inner.add_edge(source_id, source_output_index, target_id, target_input_index, policy)?;
+        Ok(serde_wasm_bindgen::to_value(&update)?)
+    }
+
+    /// Returns `null` if the connection is allowed, or the error `add_edge` would throw.
+    pub fn can_connect(&self, source_id: String, source_output_index: usize, target_id: String, target_input_index: usize) -> JsValue {
+        match self.inner.can_connect(source_id, source_output_index, target_id, target_input_index) {
+            Ok(()) => JsValue::NULL,
+            Err(err) => err.into(),
+        }
+    }
+
+    pub fn remove_edge(&mut self, source_id: String, source_output_index: usize, target_id: String, target_input_index: usize) -> Result<Vec<String>, EngineError> {
+        self.inner.remove_edge(source_id, source_output_index, target_id, target_input_index)
+    }
+
+    pub fn update_node_data(&mut self, id: &str, data: JsValue) -> Result<Vec<String>, EngineError> {
+        let parsed_data: Vec<SerdeValue> = serde_wasm_bindgen::from_value(data)?;
+        self.inner.update_node_data(id, parsed_data)
+    }
+
+    pub fn evaluate_node(&mut self, id: &str) -> Result<JsValue, EngineError> {
+        let trace = self.inner.evaluate_node(id)?;
+        Ok(serde_wasm_bindgen::to_value(&trace)?)
+    }
+
+    /// Applies a list of `{ kind, ... }` ops atomically, returning the combined dirty node IDs.
+    pub fn apply_batch(&mut self, ops: JsValue) -> Result<Vec<String>, EngineError> {
+        let parsed_ops: Vec<GraphOp> = serde_wasm_bindgen::from_value(ops)?;
+        self.inner.apply_batch(parsed_ops)
+    }
+
+    /// Moves the nodes `ids` into a new group node, returning the invalidated node IDs.
+    pub fn group_nodes(&mut self, group_id: String, ids: Vec<String>) -> Result<Vec<String>, EngineError> {
+        self.inner.group_nodes(group_id, ids)
+    }
+
+    /// Dissolves a group into its inner nodes; returns `{ dirty, removed }` like `add_edge`.
+    pub fn ungroup(&mut self, id: &str) -> Result<JsValue, EngineError> {
+        let update = self.inner.ungroup(id)?;
+        Ok(serde_wasm_bindgen::to_value(&update)?)
+    }
+
+    pub fn enter_group(&mut self, id: &str) -> Result<(), EngineError> {
+        self.inner.enter_group(id)
+    }
+
+    /// Returns `{ dirty, removed }` for the enclosing graph, or `null` at the top level.
+    pub fn exit_group(&mut self) -> Result<JsValue, EngineError> {
+        match self.inner.exit_group() {
+            Some(update) => Ok(serde_wasm_bindgen::to_value(&update)?),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    pub fn scope(&self) -> Vec<String> {
+        self.inner.scope()
+    }
+
+    /// Schema of one node (per-instance for groups), or `null` if there is no such node.
+    pub fn node_schema(&self, id: &str) -> Result<JsValue, EngineError> {
+        match self.inner.node_schema(id) {
+            Some(schema) => Ok(serde_wasm_bindgen::to_value(&schema)?),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Consistency check of the current graph, returning `{ issues }`.
+    pub fn validate(&self) -> Result<JsValue, EngineError> {
+        Ok(serde_wasm_bindgen::to_value(&self.inner.validate())?)
+    }
+
+    /// Fixes the repairable issues, returning `{ repaired, remaining, dirty }`.
+    pub fn repair(&mut self) -> Result<JsValue, EngineError> {
+        Ok(serde_wasm_bindgen::to_value(&self.inner.repair())?)
+    }
+
+    /// Reverts the last step, returning `{ label?, dirty, changes }` or `null` if there is none.
+    pub fn undo(&mut self) -> Result<JsValue, EngineError> {
+        match self.inner.undo()? {
+            Some(update) => Ok(serde_wasm_bindgen::to_value(&update)?),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Re-applies the last undone step; same result shape as `undo`.
+    pub fn redo(&mut self) -> Result<JsValue, EngineError> {
+        match self.inner.redo()? {
+            Some(update) => Ok(serde_wasm_bindgen::to_value(&update)?),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    pub fn begin_group(&mut self, label: String) {
+        self.inner.begin_group(label);
+    }
+
+    pub fn end_group(&mut self) {
+        self.inner.end_group();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.inner.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.inner.can_redo()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.inner.clear_history();
+    }
+}
+
+// --- Error Conversion ---
+
+impl From<serde_wasm_bindgen::Error> for EngineError {
+    fn from(err: serde_wasm_bindgen::Error) -> Self {
+        EngineError::Deserialization { message: err.to_string() }
+    }
+}
+
+// What JS receives when a `GraphEngine` method throws
+#[derive(Serialize)]
+struct ErrorPayload {
+    code: &'static str,
+    message: String,
+    #[serde(rename = "nodeIds")]
+    node_ids: Vec<String>,
+    sockets: Vec<SocketRef>,
+}
+
+impl From<EngineError> for JsValue {
+    fn from(err: EngineError) -> Self {
+        let payload = ErrorPayload {
+            code: err.code(),
+            message: err.to_string(),
+            node_ids: err.node_ids(),
+            sockets: err.sockets(),
+        };
+        serde_wasm_bindgen::to_value(&payload).unwrap_or_else(|_| JsValue::from_str(&payload.message))
+    }
+}